@@ -0,0 +1,11 @@
+// The number of arrangements reaching a joltage is the sum of the numbers reaching the joltages
+// just below it, so counting only needs zero, one and an addition that notices when it overflows.
+// num-traits has those for every integer type, and num-bigint implements them for `BigUint`, so
+// any of them can count: fixed-width integers fail on long chains, `BigUint` never does.
+
+use num_traits::{CheckedAdd, One, Zero};
+
+/// A number type arrangements can be counted with.
+pub trait ArrangementCount: Clone + Zero + One + CheckedAdd {}
+
+impl<T: Clone + Zero + One + CheckedAdd> ArrangementCount for T {}
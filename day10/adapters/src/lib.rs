@@ -0,0 +1,426 @@
+// The adapters are used in order of joltage, starting from the charging outlet and ending at the
+// device's built-in adapter, so the whole problem is about the sorted list of joltages. Every
+// adapter takes an input 1 to `tolerance` jolts lower than its rating (3 in the puzzle); a larger
+// gap anywhere breaks the chain.
+//
+// To count the arrangements, the number of ways to reach a joltage is the sum of the ways to reach
+// every joltage that can plug into it, and those are exactly the ones at most `tolerance` jolts
+// lower. Going in order, they form a window that slides along the chain.
+
+pub mod count;
+
+use count::ArrangementCount;
+use std::collections::{BTreeMap, VecDeque};
+use std::error::Error;
+use std::fmt;
+use std::io::BufRead;
+
+/// Joltages of the puzzle: what an adapter accepts, and where the chain starts and ends. Both the
+/// tolerance and the device offset have to be at least 1, otherwise no chain could be valid and
+/// the device wouldn't be the highest joltage.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChainConfig {
+    /// Largest difference of joltage an adapter accepts.
+    pub tolerance: i64,
+    /// Joltage of the charging outlet.
+    pub outlet: i64,
+    /// How much higher the device's adapter is rated than the highest adapter in the bag.
+    pub device_offset: i64,
+}
+
+impl Default for ChainConfig {
+    fn default() -> ChainConfig {
+        ChainConfig {
+            tolerance: 3,
+            outlet: 0,
+            device_offset: 3,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ChainError {
+    /// The joltages at `position` and `position + 1` of the chain differ by less than 1 or more
+    /// than the tolerance.
+    Gap {
+        position: usize,
+        from: i64,
+        to: i64,
+    },
+    /// No arrangement reaches the device, because nothing connects `from` jolts to `to` jolts.
+    Unreachable {
+        from: i64,
+        to: i64,
+    },
+    /// The tolerance or the device offset of the configuration is less than 1.
+    InvalidConfig(String),
+    /// The device's joltage or the number of arrangements doesn't fit in its type.
+    Overflow,
+    ZeroModulus,
+    Parse {
+        line: usize,
+        text: String,
+    },
+    Io(String),
+}
+
+impl fmt::Display for ChainError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ChainError::Gap { position, from, to } => write!(
+                f,
+                "the chain breaks at position {}: {} jolts can't go into {} jolts",
+                position, from, to
+            ),
+            ChainError::Unreachable { from, to } => write!(
+                f,
+                "the device can't be reached: nothing connects {} jolts to {} jolts",
+                from, to
+            ),
+            ChainError::InvalidConfig(reason) => {
+                write!(f, "invalid chain configuration: {}", reason)
+            }
+            ChainError::Overflow => {
+                write!(
+                    f,
+                    "the device's joltage or the number of arrangements overflows"
+                )
+            }
+            ChainError::ZeroModulus => write!(f, "can't count modulo 0"),
+            ChainError::Parse { line, text } => {
+                write!(f, "line {}: invalid joltage: {}", line, text)
+            }
+            ChainError::Io(err) => write!(f, "couldn't read the adapters: {}", err),
+        }
+    }
+}
+
+impl Error for ChainError {}
+
+/// Reads one joltage per line, skipping blank lines.
+pub fn parse_adapters<R: BufRead>(reader: R) -> Result<Vec<i64>, ChainError> {
+    let mut adapters = Vec::new();
+    for (i, line) in reader.lines().enumerate() {
+        let line = line.map_err(|err| ChainError::Io(err.to_string()))?;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let adapter = line.parse().map_err(|_| ChainError::Parse {
+            line: i + 1,
+            text: line.to_string(),
+        })?;
+        adapters.push(adapter);
+    }
+    Ok(adapters)
+}
+
+/// Every adapter in the bag, in order, from the outlet to the device.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AdapterChain {
+    joltages: Vec<i64>,
+    config: ChainConfig,
+}
+
+impl AdapterChain {
+    /// Sorts a copy of the adapters between the outlet, rated 0 jolts, and the device, rated 3
+    /// jolts more than the highest adapter. Fails if the device's joltage overflows.
+    pub fn new(adapters: &[i64]) -> Result<AdapterChain, ChainError> {
+        AdapterChain::with_config(adapters, ChainConfig::default())
+    }
+
+    /// Same as `new`, with the joltages of `config`. Fails if its tolerance or device offset is
+    /// less than 1, or if the device's joltage overflows.
+    pub fn with_config(adapters: &[i64], config: ChainConfig) -> Result<AdapterChain, ChainError> {
+        if config.tolerance < 1 {
+            return Err(ChainError::InvalidConfig(format!(
+                "the tolerance has to be at least 1, not {}",
+                config.tolerance
+            )));
+        }
+        if config.device_offset < 1 {
+            return Err(ChainError::InvalidConfig(format!(
+                "the device offset has to be at least 1, not {}",
+                config.device_offset
+            )));
+        }
+        let mut sorted = adapters.to_vec();
+        sorted.sort_unstable();
+        let highest = sorted.last().copied().unwrap_or(config.outlet);
+        let device = highest
+            .max(config.outlet)
+            .checked_add(config.device_offset)
+            .ok_or(ChainError::Overflow)?;
+        let mut joltages = Vec::with_capacity(sorted.len() + 2);
+        joltages.push(config.outlet);
+        joltages.extend(sorted);
+        joltages.push(device);
+        Ok(AdapterChain { joltages, config })
+    }
+
+    pub fn config(&self) -> ChainConfig {
+        self.config
+    }
+
+    /// The outlet, the adapters and the device, in order.
+    pub fn joltages(&self) -> &[i64] {
+        &self.joltages
+    }
+
+    pub fn adapters(&self) -> &[i64] {
+        &self.joltages[1..self.joltages.len() - 1]
+    }
+
+    pub fn outlet(&self) -> i64 {
+        self.joltages[0]
+    }
+
+    pub fn device(&self) -> i64 {
+        self.joltages[self.joltages.len() - 1]
+    }
+
+    /// Difference of joltage between every element of the chain and the next one.
+    pub fn differences(&self) -> impl Iterator<Item = i64> + '_ {
+        self.joltages.windows(2).map(|x| x[1] - x[0])
+    }
+
+    /// How many times every difference of joltage appears in the chain.
+    pub fn histogram(&self) -> BTreeMap<i64, usize> {
+        let mut histogram = BTreeMap::new();
+        for difference in self.differences() {
+            *histogram.entry(difference).or_insert(0) += 1;
+        }
+        histogram
+    }
+
+    /// Number of times `difference` appears in the chain.
+    pub fn count(&self, difference: i64) -> usize {
+        self.differences().filter(|&x| x == difference).count()
+    }
+
+    /// Fails with the first place where the next joltage isn't 1 to `tolerance` jolts higher.
+    pub fn validate(&self) -> Result<(), ChainError> {
+        match self
+            .differences()
+            .position(|x| !(1..=self.config.tolerance).contains(&x))
+        {
+            Some(position) => Err(ChainError::Gap {
+                position,
+                from: self.joltages[position],
+                to: self.joltages[position + 1],
+            }),
+            None => Ok(()),
+        }
+    }
+
+    /// Number of ways to connect the outlet to the device with some of the adapters. Adapters
+    /// rated at or below the outlet can't be part of any arrangement and are left out.
+    pub fn arrangements<T: ArrangementCount>(&self) -> Result<T, ChainError> {
+        self.count_with(T::zero(), T::one(), T::checked_add)
+    }
+
+    /// Number of arrangements modulo `modulus`, which never overflows.
+    pub fn arrangements_modulo(&self, modulus: u64) -> Result<u64, ChainError> {
+        if modulus == 0 {
+            return Err(ChainError::ZeroModulus);
+        }
+        let add = |a: &u64, b: &u64| {
+            Some(((u128::from(*a) + u128::from(*b)) % u128::from(modulus)) as u64)
+        };
+        self.count_with(0, 1 % modulus, add)
+    }
+
+    /// The first gap wider than the tolerance among the joltages above the outlet, if any.
+    fn unreachable(&self) -> Option<ChainError> {
+        let outlet = self.outlet();
+        let mut previous = outlet;
+        for &joltage in self.joltages[1..].iter().filter(|&&x| x > outlet) {
+            if joltage - previous > self.config.tolerance {
+                return Some(ChainError::Unreachable {
+                    from: previous,
+                    to: joltage,
+                });
+            }
+            previous = joltage;
+        }
+        None
+    }
+
+    fn count_with<T: Clone, F: Fn(&T, &T) -> Option<T>>(
+        &self,
+        zero: T,
+        one: T,
+        add: F,
+    ) -> Result<T, ChainError> {
+        if let Some(err) = self.unreachable() {
+            return Err(err);
+        }
+        let outlet = self.outlet();
+        // Joltages at most `tolerance` below the current one, with the ways to reach each
+        let mut window: VecDeque<(i64, T)> = VecDeque::new();
+        window.push_back((outlet, one));
+        for &joltage in self.joltages[1..].iter().filter(|&&x| x > outlet) {
+            while let Some((lowest, _)) = window.front() {
+                if joltage - lowest <= self.config.tolerance {
+                    break;
+                }
+                window.pop_front();
+            }
+            let mut ways = zero.clone();
+            for (_, x) in window.iter().filter(|(lower, _)| *lower < joltage) {
+                ways = add(&ways, x).ok_or(ChainError::Overflow)?;
+            }
+            window.push_back((joltage, ways));
+        }
+        // The device is above the outlet, so the window always ends with it
+        Ok(window.pop_back().map(|(_, ways)| ways).unwrap_or(zero))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::io::BufReader;
+
+    fn example_chain(path: &str) -> AdapterChain {
+        let adapters = parse_adapters(BufReader::new(File::open(path).unwrap())).unwrap();
+        AdapterChain::new(&adapters).unwrap()
+    }
+
+    #[test]
+    fn test_example_inputs() {
+        let chain = example_chain("../input_example");
+        assert_eq!(chain.adapters().len(), 11);
+        assert_eq!((chain.outlet(), chain.device()), (0, 22));
+        assert_eq!(chain.validate(), Ok(()));
+        let histogram: Vec<(i64, usize)> = chain.histogram().into_iter().collect();
+        assert_eq!(histogram, vec![(1, 7), (3, 5)]);
+
+        let chain = example_chain("../input_example_2");
+        assert_eq!(
+            (chain.count(1), chain.count(2), chain.count(3)),
+            (22, 0, 10)
+        );
+    }
+
+    #[test]
+    fn test_breaks() {
+        let adapters = [1, 2, 9, 10];
+        let chain = AdapterChain::new(&adapters).unwrap();
+        assert_eq!(adapters, [1, 2, 9, 10]);
+        assert_eq!(
+            chain.validate(),
+            Err(ChainError::Gap {
+                position: 2,
+                from: 2,
+                to: 9
+            })
+        );
+        assert_eq!(
+            AdapterChain::new(&[1, 1]).unwrap().validate(),
+            Err(ChainError::Gap {
+                position: 1,
+                from: 1,
+                to: 1
+            })
+        );
+        assert_eq!(AdapterChain::new(&[]).unwrap().joltages(), &[0, 3]);
+        assert_eq!(AdapterChain::new(&[2, 4]).unwrap().count(1), 0);
+        assert_eq!(
+            AdapterChain::new(&[1, 2, 9, 10])
+                .unwrap()
+                .arrangements::<u64>(),
+            Err(ChainError::Unreachable { from: 2, to: 9 })
+        );
+    }
+
+    #[test]
+    fn test_config() {
+        let config = ChainConfig {
+            tolerance: 2,
+            outlet: 10,
+            device_offset: 1,
+        };
+        let chain = AdapterChain::with_config(&[14, 11, 12, 13], config).unwrap();
+        assert_eq!(chain.joltages(), &[10, 11, 12, 13, 14, 15]);
+        assert_eq!(chain.validate(), Ok(()));
+        // Ways to climb 5 steps taking 1 or 2 at a time
+        assert_eq!(chain.arrangements::<u64>(), Ok(8));
+        let chain = AdapterChain::with_config(&[11, 14], config).unwrap();
+        assert!(chain.validate().is_err());
+        assert_eq!(
+            chain.arrangements::<u64>(),
+            Err(ChainError::Unreachable { from: 11, to: 14 })
+        );
+
+        let config = ChainConfig {
+            tolerance: 5,
+            ..ChainConfig::default()
+        };
+        assert_eq!(
+            AdapterChain::with_config(&[5, 9], config).map(|x| x.validate()),
+            Ok(Ok(()))
+        );
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        for (tolerance, device_offset) in [(0, 3), (-1, 3), (3, 0), (3, -2)].iter() {
+            let config = ChainConfig {
+                tolerance: *tolerance,
+                device_offset: *device_offset,
+                ..ChainConfig::default()
+            };
+            assert!(matches!(
+                AdapterChain::with_config(&[1, 2], config),
+                Err(ChainError::InvalidConfig(_))
+            ));
+        }
+        assert_eq!(
+            AdapterChain::with_config(&[i64::MAX - 1], ChainConfig::default()),
+            Err(ChainError::Overflow)
+        );
+        assert_eq!(
+            AdapterChain::new(&[9223372036854775806]),
+            Err(ChainError::Overflow)
+        );
+        // Without adapters, the device still goes right after the outlet
+        let config = ChainConfig {
+            outlet: 5,
+            device_offset: 2,
+            ..ChainConfig::default()
+        };
+        let chain = AdapterChain::with_config(&[], config).unwrap();
+        assert_eq!(chain.joltages(), &[5, 7]);
+        assert_eq!(chain.arrangements::<u64>(), Ok(1));
+    }
+
+    #[test]
+    fn test_arrangements() {
+        let chain = example_chain("../input_example");
+        assert_eq!(chain.arrangements::<u64>(), Ok(8));
+        let chain = example_chain("../input_example_2");
+        assert_eq!(chain.arrangements::<u64>(), Ok(19208));
+        assert_eq!(chain.arrangements::<u8>(), Err(ChainError::Overflow));
+    }
+
+    #[test]
+    fn test_arrangements_modulo() {
+        for path in ["../input_example", "../input_example_2"].iter() {
+            let chain = example_chain(path);
+            let arrangements = chain.arrangements::<u128>().unwrap();
+            for &modulus in [1, 2, 7, 1000, 19208, u64::MAX].iter() {
+                assert_eq!(
+                    chain.arrangements_modulo(modulus),
+                    Ok((arrangements % u128::from(modulus)) as u64)
+                );
+            }
+            assert_eq!(chain.arrangements_modulo(0), Err(ChainError::ZeroModulus));
+        }
+        assert_eq!(
+            AdapterChain::new(&[]).unwrap().arrangements_modulo(1),
+            Ok(0)
+        );
+    }
+}
@@ -100,39 +100,30 @@
 // and your device. What is the number of 1-jolt differences multiplied by the number of 3-jolt
 // differences?
 
-use std::collections::HashMap;
-
-fn part1(input: &mut Vec<i64>) -> i64 {
-    input.push(0); // seat outlet jolt rating
-    input.push(input.iter().copied().max().unwrap() + 3); // always the built-in adapter is max rating of adapter + 3
-    input.sort_unstable();
-    let iter = input.iter();
-    let hmap = iter
-        .zip(input.iter().skip(1))
-        .map(|(&x, &y)| (x - y).abs())
-        .fold(HashMap::<i64, i64>::new(), |mut acc, item| {
-            let counter = acc.entry(item).or_insert(0);
-            *counter += 1;
-            acc
-        });
-    hmap.get(&3).unwrap() * hmap.get(&1).unwrap()
+use adapters::{AdapterChain, ChainError};
+
+/// Number of 1-jolt differences multiplied by the number of 3-jolt differences in the chain.
+pub fn part1(input: &[i64]) -> Result<usize, ChainError> {
+    let chain = AdapterChain::new(input)?;
+    chain.validate()?;
+    Ok(chain.count(1) * chain.count(3))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use adapters::parse_adapters;
     use std::io;
-    use std::io::BufRead;
 
     #[test]
     fn part1_test() {
         let stdin = io::stdin();
-        let mut input: Vec<i64> = stdin
-            .lock()
-            .lines()
-            .filter_map(|x| x.ok())
-            .filter_map(|x| x.parse::<i64>().ok())
-            .collect();
-        assert_eq!(part1(&mut input), 1914);
+        let input = parse_adapters(stdin.lock()).unwrap();
+        assert_eq!(part1(&input), Ok(1914));
+    }
+
+    #[test]
+    fn part1_overflow_test() {
+        assert_eq!(part1(&[9223372036854775806]), Err(ChainError::Overflow));
     }
 } /* tests */
@@ -53,46 +53,30 @@
 
 // What is the total number of distinct ways you can arrange the adapters to connect the charging outlet to your device?
 
-use std::collections::HashMap;
+use adapters::count::ArrangementCount;
+use adapters::{AdapterChain, ChainError};
 
-fn part2(input: &mut Vec<i64>) -> Option<i64> {
-    if let Some(&max) = &mut input.iter().max() {
-        input.push(max + 3); // built in adapter
-    }
-    input.sort_unstable();
-    let mut map: HashMap<i64, i64> = HashMap::new();
-    map.insert(0, 1);
-    let output_map = input.iter().fold(map, |mut acc, &v| {
-        match (acc.get(&(v - 3)), acc.get(&(v - 2)), acc.get(&(v - 1))) {
-            (None, None, None) => acc.insert(v, 0),
-            (None, None, Some(&z)) => acc.insert(v, z),
-            (None, Some(&y), None) => acc.insert(v, y),
-            (None, Some(&y), Some(&z)) => acc.insert(v, y + z),
-            (Some(&x), None, None) => acc.insert(v, x),
-            (Some(&x), None, Some(&z)) => acc.insert(v, x + z),
-            (Some(&x), Some(&y), None) => acc.insert(v, x + y),
-            (Some(&x), Some(&y), Some(&z)) => acc.insert(v, x + y + z),
-        };
-        acc
-    });
-    output_map.get(input.iter().max().unwrap()).copied()
+/// Number of ways to arrange the adapters to connect the outlet to the device.
+pub fn part2<T: ArrangementCount>(input: &[i64]) -> Result<T, ChainError> {
+    AdapterChain::new(input)?.arrangements()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use adapters::parse_adapters;
+    use num_bigint::BigUint;
     use std::io;
-    use std::io::BufRead;
 
     #[test]
     fn test_part2() {
         let stdin = io::stdin();
-        let mut input: Vec<i64> = stdin
-            .lock()
-            .lines()
-            .filter_map(|x| x.ok())
-            .filter_map(|x| x.parse::<i64>().ok())
-            .collect();
-        assert_eq!(part2(&mut input), Some(9256148959232));
+        let input = parse_adapters(stdin.lock()).unwrap();
+        assert_eq!(part2::<u64>(&input), Ok(9256148959232));
+        assert_eq!(
+            part2::<BigUint>(&input).map(|x| x.to_string()),
+            Ok("9256148959232".to_string())
+        );
+        assert_eq!(part2::<u32>(&input), Err(ChainError::Overflow));
     }
 }
@@ -0,0 +1,276 @@
+use crate::Passport;
+use std::fmt;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+const CM_PER_INCH_TIMES_100: u32 = 254;
+
+const CSV_HEADER: &str = "valid,byr,iyr,eyr,hgt_cm,hcl,ecl,pid,cid";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Json,
+    Csv,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Format, String> {
+        match s {
+            "json" => Ok(Format::Json),
+            "csv" => Ok(Format::Csv),
+            _ => Err(format!("unknown export format: {}", s)),
+        }
+    }
+}
+
+/// Which passports end up in the export.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Selection {
+    Valid,
+    Invalid,
+    All,
+}
+
+impl FromStr for Selection {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Selection, String> {
+        match s {
+            "valid" => Ok(Selection::Valid),
+            "invalid" => Ok(Selection::Invalid),
+            "all" => Ok(Selection::All),
+            _ => Err(format!("unknown passport selection: {}", s)),
+        }
+    }
+}
+
+impl Selection {
+    fn includes(self, valid: bool) -> bool {
+        match self {
+            Selection::Valid => valid,
+            Selection::Invalid => !valid,
+            Selection::All => true,
+        }
+    }
+}
+
+/// A passport with its values converted to a canonical form. Values that are missing or can't be
+/// normalized are `None`.
+#[derive(Debug, PartialEq)]
+pub struct NormalizedPassport {
+    pub valid: bool,
+    pub byr: Option<u32>,
+    pub iyr: Option<u32>,
+    pub eyr: Option<u32>,
+    pub hgt_cm: Option<u32>,
+    pub hcl: Option<String>,
+    pub ecl: Option<String>,
+    pub pid: Option<String>,
+    pub cid: Option<String>,
+}
+
+impl NormalizedPassport {
+    fn new(passport: &Passport) -> NormalizedPassport {
+        NormalizedPassport {
+            valid: passport.is_valid(),
+            byr: passport.get("byr").and_then(|x| x.parse().ok()),
+            iyr: passport.get("iyr").and_then(|x| x.parse().ok()),
+            eyr: passport.get("eyr").and_then(|x| x.parse().ok()),
+            hgt_cm: passport.get("hgt").and_then(normalize_hgt),
+            hcl: passport.get("hcl").and_then(normalize_hcl),
+            ecl: passport.get("ecl").map(|x| x.to_string()),
+            pid: passport.get("pid").and_then(normalize_pid),
+            cid: passport.get("cid").map(|x| x.to_string()),
+        }
+    }
+}
+
+/// Converts a height to centimeters, rounding inches to the nearest centimeter.
+fn normalize_hgt(hgt: &str) -> Option<u32> {
+    if let Some(cm) = hgt.strip_suffix("cm") {
+        cm.parse().ok()
+    } else if let Some(inches) = hgt.strip_suffix("in") {
+        inches
+            .parse::<u32>()
+            .ok()
+            .and_then(|x| x.checked_mul(CM_PER_INCH_TIMES_100)?.checked_add(50))
+            .map(|x| x / 100)
+    } else {
+        None
+    }
+}
+
+fn normalize_hcl(hcl: &str) -> Option<String> {
+    match hcl.strip_prefix('#') {
+        Some(hex) if !hex.is_empty() && hex.chars().all(|c| c.is_ascii_hexdigit()) => {
+            Some(hcl.to_ascii_lowercase())
+        }
+        _ => None,
+    }
+}
+
+fn normalize_pid(pid: &str) -> Option<String> {
+    if pid.is_empty() || !pid.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let digits = pid.trim_start_matches('0');
+    Some(format!(
+        "{:0>width$}",
+        digits,
+        width = crate::VALID_PID_DIGITS
+    ))
+}
+
+/// Wraps an optional value so it is written as a JSON value.
+struct JsonValue<'a, T>(&'a Option<T>);
+
+impl fmt::Display for JsonValue<'_, u32> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.0 {
+            Some(x) => write!(f, "{}", x),
+            None => write!(f, "null"),
+        }
+    }
+}
+
+impl fmt::Display for JsonValue<'_, String> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self.0 {
+            Some(s) => s,
+            None => return write!(f, "null"),
+        };
+        write!(f, "\"")?;
+        for c in s.chars() {
+            match c {
+                '"' => write!(f, "\\\"")?,
+                '\\' => write!(f, "\\\\")?,
+                c if c.is_control() => write!(f, "\\u{:04x}", c as u32)?,
+                c => write!(f, "{}", c)?,
+            }
+        }
+        write!(f, "\"")
+    }
+}
+
+/// Wraps an optional value so it is written as a CSV cell, quoting it when needed.
+struct CsvValue<'a, T>(&'a Option<T>);
+
+impl<T: fmt::Display> fmt::Display for CsvValue<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let s = match self.0 {
+            Some(x) => x.to_string(),
+            None => return Ok(()),
+        };
+        if s.contains([',', '"', '\n']) {
+            write!(f, "\"{}\"", s.replace('"', "\"\""))
+        } else {
+            write!(f, "{}", s)
+        }
+    }
+}
+
+fn write_json_line<W: Write>(out: &mut W, p: &NormalizedPassport) -> io::Result<()> {
+    writeln!(
+        out,
+        "{{\"valid\":{},\"byr\":{},\"iyr\":{},\"eyr\":{},\"hgt_cm\":{},\"hcl\":{},\"ecl\":{},\"pid\":{},\"cid\":{}}}",
+        p.valid,
+        JsonValue(&p.byr),
+        JsonValue(&p.iyr),
+        JsonValue(&p.eyr),
+        JsonValue(&p.hgt_cm),
+        JsonValue(&p.hcl),
+        JsonValue(&p.ecl),
+        JsonValue(&p.pid),
+        JsonValue(&p.cid),
+    )
+}
+
+fn write_csv_line<W: Write>(out: &mut W, p: &NormalizedPassport) -> io::Result<()> {
+    writeln!(
+        out,
+        "{},{},{},{},{},{},{},{},{}",
+        p.valid,
+        CsvValue(&p.byr),
+        CsvValue(&p.iyr),
+        CsvValue(&p.eyr),
+        CsvValue(&p.hgt_cm),
+        CsvValue(&p.hcl),
+        CsvValue(&p.ecl),
+        CsvValue(&p.pid),
+        CsvValue(&p.cid),
+    )
+}
+
+/// Writes the selected passports either as JSON lines or as CSV with a header row.
+pub fn write_passports<W: Write>(
+    out: &mut W,
+    passports: &[Passport],
+    format: Format,
+    selection: Selection,
+) -> io::Result<()> {
+    if format == Format::Csv {
+        writeln!(out, "{}", CSV_HEADER)?;
+    }
+    for passport in passports {
+        let normalized = NormalizedPassport::new(passport);
+        if !selection.includes(normalized.valid) {
+            continue;
+        }
+        match format {
+            Format::Json => write_json_line(out, &normalized)?,
+            Format::Csv => write_csv_line(out, &normalized)?,
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn export(input: &str, format: Format, selection: Selection) -> String {
+        let passports: Vec<Passport> = input.split("\n\n").map(Passport::parse).collect();
+        let mut out = Vec::new();
+        write_passports(&mut out, &passports, format, selection).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_normalize_hgt() {
+        assert_eq!(normalize_hgt("183cm"), Some(183));
+        assert_eq!(normalize_hgt("74in"), Some(188));
+        assert_eq!(normalize_hgt("170"), None);
+        assert_eq!(normalize_hgt("99999999in"), None);
+        assert_eq!(normalize_hgt("16909320in"), None);
+    }
+
+    #[test]
+    fn test_normalize_pid() {
+        assert_eq!(normalize_pid("087499704"), Some("087499704".to_string()));
+        assert_eq!(normalize_pid("1234"), Some("000001234".to_string()));
+        assert_eq!(normalize_pid("186cm"), None);
+    }
+
+    #[test]
+    fn test_export_json() {
+        let input = "pid:087499704 hgt:74in ecl:grn iyr:2012 eyr:2030 byr:1980\nhcl:#623A2F\n\n\
+                     eyr:1972 cid:100\nhcl:#18171d ecl:amb hgt:170 pid:186cm iyr:2018 byr:1926";
+        let expected = "{\"valid\":true,\"byr\":1980,\"iyr\":2012,\"eyr\":2030,\"hgt_cm\":188,\
+                        \"hcl\":\"#623a2f\",\"ecl\":\"grn\",\"pid\":\"087499704\",\"cid\":null}\n";
+        assert_eq!(export(input, Format::Json, Selection::Valid), expected);
+    }
+
+    #[test]
+    fn test_export_csv() {
+        let input = "eyr:1972 cid:100\nhcl:#18171d ecl:amb hgt:170 pid:186cm iyr:2018 byr:1926";
+        let expected = "valid,byr,iyr,eyr,hgt_cm,hcl,ecl,pid,cid\n\
+                        false,1926,2018,1972,,#18171d,amb,,100\n";
+        assert_eq!(export(input, Format::Csv, Selection::All), expected);
+        assert_eq!(
+            export(input, Format::Csv, Selection::Valid),
+            "valid,byr,iyr,eyr,hgt_cm,hcl,ecl,pid,cid\n"
+        );
+    }
+}
@@ -1,3 +1,8 @@
+mod export;
+
+use export::{Format, Selection};
+use std::env;
+use std::error::Error;
 use std::io;
 use std::io::prelude::*;
 
@@ -108,11 +113,81 @@ fn is_valid_pid(pid: &str) -> bool {
     pid.len() == VALID_PID_DIGITS && pid.parse::<usize>().is_ok()
 }
 
-fn main() {
+/// A passport as found in the batch file, keeping its `key:value` pairs in input order.
+#[derive(Debug)]
+struct Passport {
+    fields: Vec<(String, String)>,
+}
+
+impl Passport {
+    fn parse(s: &str) -> Passport {
+        let fields = s
+            .split_whitespace()
+            .map(|field| {
+                let mut iter = field.splitn(2, ':');
+                let key = iter.next().unwrap_or_default().to_string();
+                let value = iter.next().unwrap_or_default().to_string();
+                (key, value)
+            })
+            .collect();
+        Passport { fields }
+    }
+
+    fn get(&self, key: &str) -> Option<&str> {
+        self.fields
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+
+    fn is_valid(&self) -> bool {
+        FIELDS.iter().all(|&key| self.get(key).is_some())
+            && self.fields.iter().all(|(key, value)| match key.as_str() {
+                "byr" => is_valid_byr(value),
+                "iyr" => is_valid_iyr(value),
+                "eyr" => is_valid_eyr(value),
+                "hgt" => is_valid_hgt(value),
+                "hcl" => is_valid_hcl(value),
+                "ecl" => is_valid_ecl(value),
+                "pid" => is_valid_pid(value),
+                "cid" => true,
+                _ => false,
+            })
+    }
+}
+
+fn parse_args() -> Result<Option<(Format, Selection)>, Box<dyn Error>> {
+    let mut format = None;
+    let mut selection = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--export" => {
+                let value = args.next().ok_or("--export needs a format: json or csv")?;
+                format = Some(value.parse()?);
+            }
+            "--select" => {
+                let value = args
+                    .next()
+                    .ok_or("--select needs one of: valid, invalid or all")?;
+                selection = Some(value.parse()?);
+            }
+            _ => return Err(format!("unknown argument: {}", arg).into()),
+        }
+    }
+    match (format, selection) {
+        (Some(format), selection) => Ok(Some((format, selection.unwrap_or(Selection::All)))),
+        (None, Some(_)) => Err("--select only applies to --export".into()),
+        (None, None) => Ok(None),
+    }
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let export = parse_args()?;
     let stdin = io::stdin();
-    let vec: Vec<String> = stdin.lock().lines().filter_map(Result::ok).collect();
+    let vec: Vec<String> = stdin.lock().lines().map_while(Result::ok).collect();
 
-    let count = vec
+    let passports: Vec<Passport> = vec
         .iter()
         .enumerate()
         .scan(String::new(), |state, (i, val)| {
@@ -123,33 +198,26 @@ fn main() {
                 Some(res)
             } else if state.is_empty() {
                 *state = val.to_string();
-                return_last_state(i, vec.len(), &state)
+                return_last_state(i, vec.len(), state)
             } else {
                 let line = format!("{} {}", *state, val);
                 *state = line;
-                return_last_state(i, vec.len(), &state)
+                return_last_state(i, vec.len(), state)
             }
         })
         .filter(|x| x != SKIP)
-        .filter(|x| FIELDS.iter().all(|&key| x.contains(key)))
-        .filter(|x| {
-            x.split_whitespace().all(|field| {
-                let key = field.split(':').next();
-                let value = field.split(':').nth(1).unwrap();
-                match key {
-                    Some("byr") => is_valid_byr(&value),
-                    Some("iyr") => is_valid_iyr(&value),
-                    Some("eyr") => is_valid_eyr(&value),
-                    Some("hgt") => is_valid_hgt(&value),
-                    Some("hcl") => is_valid_hcl(&value),
-                    Some("ecl") => is_valid_ecl(&value),
-                    Some("pid") => is_valid_pid(&value),
-                    Some("cid") => true,
-                    _ => false,
-                }
-            })
-        })
-        .count();
+        .map(|x| Passport::parse(&x))
+        .collect();
 
-    println!("Number of valid passports is: {}", count);
+    match export {
+        Some((format, selection)) => {
+            let stdout = io::stdout();
+            export::write_passports(&mut stdout.lock(), &passports, format, selection)?;
+        }
+        None => {
+            let count = passports.iter().filter(|x| x.is_valid()).count();
+            println!("Number of valid passports is: {}", count);
+        }
+    }
+    Ok(())
 }
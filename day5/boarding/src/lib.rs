@@ -0,0 +1,429 @@
+// A boarding pass like `FBFBBFFRLR` is the seat ID written in binary: the first letters are the
+// row (F = 0, B = 1) and the last letters are the column (L = 0, R = 1). So the seat ID, which is
+// row * number of columns + column, is just those bits read as one number. The puzzle's aircraft
+// has 7 row bits and 3 column bits, but any `Geometry` works the same way.
+
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+const DEFAULT_ROW_BITS: u32 = 7;
+const DEFAULT_COLUMN_BITS: u32 = 3;
+const MAX_SEAT_BITS: u32 = 31;
+
+const OCCUPIED_SEAT: char = '#';
+const FREE_SEAT: char = '.';
+const YOUR_SEAT: char = 'O';
+
+#[derive(Debug, PartialEq)]
+pub enum BoardingPassError {
+    WrongLength { expected: usize, found: usize },
+    InvalidLetter { position: usize, letter: char },
+    SeatIdOutOfRange(u32),
+    ExcludedRow(u32),
+    InvalidGeometry(String),
+}
+
+impl fmt::Display for BoardingPassError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BoardingPassError::WrongLength { expected, found } => write!(
+                f,
+                "boarding pass should have {} letters but has {}",
+                expected, found
+            ),
+            BoardingPassError::InvalidLetter { position, letter } => write!(
+                f,
+                "invalid letter '{}' at position {} of boarding pass",
+                letter, position
+            ),
+            BoardingPassError::SeatIdOutOfRange(id) => {
+                write!(f, "seat ID {} doesn't fit in a boarding pass", id)
+            }
+            BoardingPassError::ExcludedRow(row) => {
+                write!(f, "row {} doesn't exist on this aircraft", row)
+            }
+            BoardingPassError::InvalidGeometry(reason) => {
+                write!(f, "invalid aircraft geometry: {}", reason)
+            }
+        }
+    }
+}
+
+impl Error for BoardingPassError {}
+
+/// Layout of the seats of an aircraft: how many bits of the boarding pass encode the row and the
+/// column, and which rows are missing from the plane.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Geometry {
+    row_bits: u32,
+    column_bits: u32,
+    excluded_rows: Vec<u32>,
+}
+
+impl Default for Geometry {
+    fn default() -> Geometry {
+        Geometry {
+            row_bits: DEFAULT_ROW_BITS,
+            column_bits: DEFAULT_COLUMN_BITS,
+            excluded_rows: Vec::new(),
+        }
+    }
+}
+
+impl Geometry {
+    pub fn new(row_bits: u32, column_bits: u32) -> Result<Geometry, BoardingPassError> {
+        if row_bits == 0 || column_bits == 0 {
+            return Err(BoardingPassError::InvalidGeometry(
+                "rows and columns need at least one bit each".to_string(),
+            ));
+        }
+        if row_bits + column_bits > MAX_SEAT_BITS {
+            return Err(BoardingPassError::InvalidGeometry(format!(
+                "seat IDs can't have more than {} bits",
+                MAX_SEAT_BITS
+            )));
+        }
+        Ok(Geometry {
+            row_bits,
+            column_bits,
+            excluded_rows: Vec::new(),
+        })
+    }
+
+    pub fn with_excluded_rows<I: IntoIterator<Item = u32>>(mut self, rows: I) -> Geometry {
+        self.excluded_rows.extend(rows);
+        self.excluded_rows.sort_unstable();
+        self.excluded_rows.dedup();
+        self
+    }
+
+    /// Builds a geometry from command line arguments: `--row-bits N`, `--column-bits N` and
+    /// `--exclude-rows a,b,c`. Anything not given keeps the puzzle's 128 x 8 layout.
+    pub fn from_args<I: Iterator<Item = String>>(mut args: I) -> Result<Geometry, Box<dyn Error>> {
+        let mut row_bits = DEFAULT_ROW_BITS;
+        let mut column_bits = DEFAULT_COLUMN_BITS;
+        let mut excluded_rows = Vec::new();
+        while let Some(arg) = args.next() {
+            let value = args
+                .next()
+                .ok_or_else(|| format!("missing value for {}", arg))?;
+            match arg.as_str() {
+                "--row-bits" => row_bits = value.parse()?,
+                "--column-bits" => column_bits = value.parse()?,
+                "--exclude-rows" => {
+                    for row in value.split(',') {
+                        excluded_rows.push(row.trim().parse()?);
+                    }
+                }
+                _ => return Err(format!("unknown argument: {}", arg).into()),
+            }
+        }
+        Ok(Geometry::new(row_bits, column_bits)?.with_excluded_rows(excluded_rows))
+    }
+
+    pub fn num_rows(&self) -> u32 {
+        1 << self.row_bits
+    }
+
+    pub fn num_columns(&self) -> u32 {
+        1 << self.column_bits
+    }
+
+    pub fn pass_length(&self) -> usize {
+        (self.row_bits + self.column_bits) as usize
+    }
+
+    pub fn is_excluded_row(&self, row: u32) -> bool {
+        self.excluded_rows.binary_search(&row).is_ok()
+    }
+
+    pub fn pass_from_seat_id(&self, seat_id: u32) -> Result<BoardingPass, BoardingPassError> {
+        if seat_id >> (self.row_bits + self.column_bits) != 0 {
+            return Err(BoardingPassError::SeatIdOutOfRange(seat_id));
+        }
+        let pass = BoardingPass {
+            seat_id,
+            row_bits: self.row_bits,
+            column_bits: self.column_bits,
+        };
+        if self.is_excluded_row(pass.row()) {
+            return Err(BoardingPassError::ExcludedRow(pass.row()));
+        }
+        Ok(pass)
+    }
+
+    pub fn parse_pass(&self, s: &str) -> Result<BoardingPass, BoardingPassError> {
+        let found = s.chars().count();
+        if found != self.pass_length() {
+            return Err(BoardingPassError::WrongLength {
+                expected: self.pass_length(),
+                found,
+            });
+        }
+        let seat_id = s.chars().enumerate().try_fold(0, |acc, (i, c)| {
+            let bit = match (i < self.row_bits as usize, c) {
+                (true, 'F') | (false, 'L') => 0,
+                (true, 'B') | (false, 'R') => 1,
+                _ => {
+                    return Err(BoardingPassError::InvalidLetter {
+                        position: i,
+                        letter: c,
+                    })
+                }
+            };
+            Ok(acc << 1 | bit)
+        })?;
+        self.pass_from_seat_id(seat_id)
+    }
+
+    /// Every seat between the lowest and highest occupied ones that nobody has a boarding pass
+    /// for, in seat ID order. Only the gaps between occupied seats are visited, so the work doesn't
+    /// depend on the size of the aircraft. Seats in excluded rows don't exist, so they are never
+    /// missing.
+    pub fn missing_seats(&self, occupied: &[BoardingPass]) -> Vec<MissingSeat> {
+        let mut seat_ids: Vec<u32> = occupied.iter().map(|x| x.seat_id()).collect();
+        seat_ids.sort_unstable();
+        seat_ids.dedup();
+        seat_ids
+            .windows(2)
+            .flat_map(|x| {
+                let (before, after) = (x[0], x[1]);
+                (before + 1..after).map(move |seat_id| (seat_id, before, after))
+            })
+            .filter_map(|(seat_id, before, after)| {
+                let pass = self.pass_from_seat_id(seat_id).ok()?;
+                Some(MissingSeat {
+                    pass,
+                    neighbours_occupied: seat_id == before + 1 && seat_id + 1 == after,
+                })
+            })
+            .collect()
+    }
+
+    /// Draws the aircraft one row per line: `#` for occupied seats, `.` for free seats and `O` for
+    /// free seats whose neighbours are both occupied (your seat).
+    pub fn render_seat_map(&self, occupied: &[BoardingPass]) -> String {
+        let occupied_ids: HashSet<u32> = occupied.iter().map(|x| x.seat_id()).collect();
+        let your_ids: HashSet<u32> = self
+            .missing_seats(occupied)
+            .iter()
+            .filter(|x| x.neighbours_occupied)
+            .map(|x| x.pass.seat_id())
+            .collect();
+        let width = self.num_rows().to_string().len();
+        let mut map = String::new();
+        for row in (0..self.num_rows()).filter(|&x| !self.is_excluded_row(x)) {
+            map.push_str(&format!("{:>width$} ", row, width = width));
+            for column in 0..self.num_columns() {
+                let seat_id = row * self.num_columns() + column;
+                map.push(if occupied_ids.contains(&seat_id) {
+                    OCCUPIED_SEAT
+                } else if your_ids.contains(&seat_id) {
+                    YOUR_SEAT
+                } else {
+                    FREE_SEAT
+                });
+            }
+            map.push('\n');
+        }
+        map
+    }
+}
+
+/// A seat nobody has a boarding pass for.
+#[derive(Debug, PartialEq)]
+pub struct MissingSeat {
+    pub pass: BoardingPass,
+    /// Whether the seats with IDs +1 and -1 from this one are both occupied.
+    pub neighbours_occupied: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BoardingPass {
+    seat_id: u32,
+    row_bits: u32,
+    column_bits: u32,
+}
+
+impl BoardingPass {
+    /// Same as `Geometry::pass_from_seat_id` for the puzzle's 128 x 8 aircraft.
+    pub fn from_seat_id(seat_id: u32) -> Result<BoardingPass, BoardingPassError> {
+        Geometry::default().pass_from_seat_id(seat_id)
+    }
+
+    pub fn seat_id(&self) -> u32 {
+        self.seat_id
+    }
+
+    pub fn row(&self) -> u32 {
+        self.seat_id >> self.column_bits
+    }
+
+    pub fn column(&self) -> u32 {
+        self.seat_id & ((1 << self.column_bits) - 1)
+    }
+}
+
+impl FromStr for BoardingPass {
+    type Err = BoardingPassError;
+
+    fn from_str(s: &str) -> Result<BoardingPass, BoardingPassError> {
+        Geometry::default().parse_pass(s)
+    }
+}
+
+impl fmt::Display for BoardingPass {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for i in (0..self.row_bits).rev() {
+            let bit = self.row() >> i & 1;
+            write!(f, "{}", if bit == 0 { 'F' } else { 'B' })?;
+        }
+        for i in (0..self.column_bits).rev() {
+            let bit = self.column() >> i & 1;
+            write!(f, "{}", if bit == 0 { 'L' } else { 'R' })?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode() {
+        let examples = [
+            ("FBFBBFFRLR", 44, 5, 357),
+            ("BFFFBBFRRR", 70, 7, 567),
+            ("FFFBBBFRRR", 14, 7, 119),
+            ("BBFFBBFRLL", 102, 4, 820),
+        ];
+        for &(pass, row, column, seat_id) in examples.iter() {
+            let pass: BoardingPass = pass.parse().unwrap();
+            assert_eq!(pass.row(), row);
+            assert_eq!(pass.column(), column);
+            assert_eq!(pass.seat_id(), seat_id);
+        }
+    }
+
+    #[test]
+    fn test_encode() {
+        let pass = BoardingPass::from_seat_id(357).unwrap();
+        assert_eq!(pass.to_string(), "FBFBBFFRLR");
+        for seat_id in 0..1024 {
+            let pass = BoardingPass::from_seat_id(seat_id).unwrap();
+            assert_eq!(pass.to_string().parse(), Ok(pass));
+        }
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            "FBFBBFFRL".parse::<BoardingPass>(),
+            Err(BoardingPassError::WrongLength {
+                expected: 10,
+                found: 9
+            })
+        );
+        assert_eq!(
+            "FBFBBFFRLB".parse::<BoardingPass>(),
+            Err(BoardingPassError::InvalidLetter {
+                position: 9,
+                letter: 'B'
+            })
+        );
+        assert_eq!(
+            BoardingPass::from_seat_id(1024),
+            Err(BoardingPassError::SeatIdOutOfRange(1024))
+        );
+    }
+
+    #[test]
+    fn test_custom_geometry() {
+        let geometry = Geometry::new(4, 2).unwrap().with_excluded_rows(vec![0, 15]);
+        assert_eq!(geometry.pass_length(), 6);
+        let pass = geometry.parse_pass("FBFBLR").unwrap();
+        assert_eq!(pass.row(), 5);
+        assert_eq!(pass.column(), 1);
+        assert_eq!(pass.seat_id(), 21);
+        assert_eq!(pass.to_string(), "FBFBLR");
+        assert_eq!(
+            geometry.parse_pass("FBFBBFFRLR"),
+            Err(BoardingPassError::WrongLength {
+                expected: 6,
+                found: 10
+            })
+        );
+        assert_eq!(
+            geometry.parse_pass("BBBBRR"),
+            Err(BoardingPassError::ExcludedRow(15))
+        );
+    }
+
+    #[test]
+    fn test_geometry_from_args() {
+        let args = [
+            "--row-bits",
+            "5",
+            "--column-bits",
+            "2",
+            "--exclude-rows",
+            "0,31",
+        ];
+        let geometry = Geometry::from_args(args.iter().map(|x| x.to_string())).unwrap();
+        assert_eq!(
+            geometry,
+            Geometry::new(5, 2).unwrap().with_excluded_rows(vec![0, 31])
+        );
+        assert!(Geometry::from_args(vec!["--row-bits".to_string()].into_iter()).is_err());
+        assert!(Geometry::new(30, 2).is_err());
+    }
+
+    #[test]
+    fn test_missing_seats() {
+        let geometry = Geometry::new(2, 1).unwrap();
+        let occupied: Vec<BoardingPass> = vec![1, 2, 4, 6]
+            .into_iter()
+            .map(|x| geometry.pass_from_seat_id(x).unwrap())
+            .collect();
+        let missing: Vec<(u32, bool)> = geometry
+            .missing_seats(&occupied)
+            .iter()
+            .map(|x| (x.pass.seat_id(), x.neighbours_occupied))
+            .collect();
+        assert_eq!(missing, vec![(3, true), (5, true)]);
+        assert!(geometry.missing_seats(&occupied[..1]).is_empty());
+        assert_eq!(
+            geometry.render_seat_map(&occupied),
+            "0 .#\n1 #O\n2 #O\n3 #.\n"
+        );
+
+        // Only the seats between the occupied ones are visited, however large the aircraft
+        let geometry = Geometry::new(16, 15).unwrap();
+        let occupied: Vec<BoardingPass> = vec![1 << 30, (1 << 30) + 2]
+            .into_iter()
+            .map(|x| geometry.pass_from_seat_id(x).unwrap())
+            .collect();
+        let missing = geometry.missing_seats(&occupied);
+        assert_eq!(missing.len(), 1);
+        assert_eq!(missing[0].pass.seat_id(), (1 << 30) + 1);
+    }
+
+    #[test]
+    fn test_missing_seats_excluded_rows() {
+        let geometry = Geometry::new(2, 1).unwrap().with_excluded_rows(vec![0, 3]);
+        let occupied = vec![
+            geometry.pass_from_seat_id(3).unwrap(),
+            geometry.pass_from_seat_id(5).unwrap(),
+        ];
+        let missing: Vec<(u32, bool)> = geometry
+            .missing_seats(&occupied)
+            .iter()
+            .map(|x| (x.pass.seat_id(), x.neighbours_occupied))
+            .collect();
+        assert_eq!(missing, vec![(4, true)]);
+        assert_eq!(geometry.render_seat_map(&occupied), "1 .#\n2 O#\n");
+    }
+}
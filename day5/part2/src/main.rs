@@ -1,43 +1,50 @@
+use boarding::Geometry;
+use std::env;
+use std::error::Error;
 use std::io;
 use std::io::prelude::*;
 
-const NUM_ROWS: usize = 128;
-const NUM_COLUMNS: usize = 8;
+const MAP_ARG: &str = "--map";
+const ALL_MISSING_ARG: &str = "--all-missing";
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let show_map = args.iter().any(|x| x == MAP_ARG);
+    let show_all_missing = args.iter().any(|x| x == ALL_MISSING_ARG);
+    let geometry = Geometry::from_args(
+        args.into_iter()
+            .filter(|x| x != MAP_ARG && x != ALL_MISSING_ARG),
+    )?;
 
-fn main() {
     let stdin = io::stdin();
-    let vec: Vec<String> = stdin.lock().lines().filter_map(Result::ok).collect();
-    let mut ticket_ids: Vec<usize> =
-        vec.iter()
-            .map(|val| {
-                let row = val.get(..NUM_COLUMNS - 1).unwrap().chars().fold(
-                    (0, NUM_ROWS - 1),
-                    |acc, c| match c {
-                        'F' => (acc.0, (acc.0 + acc.1) / 2),
-                        _ => ((acc.0 + acc.1) / 2 + 1, acc.1),
-                    },
-                );
-                let column = val.get(NUM_COLUMNS - 1..).unwrap().chars().fold(
-                    (0, NUM_COLUMNS - 1),
-                    |acc, c| match c {
-                        'L' => (acc.0, (acc.0 + acc.1) / 2),
-                        _ => ((acc.0 + acc.1) / 2 + 1, acc.1),
-                    },
-                );
-                assert_eq!(row.0, row.1);
-                assert_eq!(column.0, column.1);
-                row.0 * NUM_COLUMNS + column.0
-            })
-            .collect();
-    ticket_ids.sort_unstable();
-    let min = ticket_ids.iter().min().unwrap();
-    let max = ticket_ids.iter().max().unwrap();
-    let range_of_ids: Vec<usize> = (*min..*max).collect();
-    let ticket_missing = ticket_ids
-        .iter()
-        .zip(range_of_ids.iter())
-        .find(|(&actual_id, &expected_id)| actual_id != expected_id)
-        .unwrap()
-        .1;
-    println!("Ticket missing is: {}", ticket_missing);
+    let mut passes = Vec::new();
+    for line in stdin.lock().lines() {
+        passes.push(geometry.parse_pass(&line?)?);
+    }
+
+    let missing_seats = geometry.missing_seats(&passes);
+    if show_all_missing {
+        for seat in &missing_seats {
+            println!(
+                "{} (seat ID {}, row {}, column {}){}",
+                seat.pass,
+                seat.pass.seat_id(),
+                seat.pass.row(),
+                seat.pass.column(),
+                if seat.neighbours_occupied {
+                    " <- neighbours occupied"
+                } else {
+                    ""
+                }
+            );
+        }
+    }
+    if show_map {
+        print!("{}", geometry.render_seat_map(&passes));
+    }
+    match missing_seats.iter().find(|x| x.neighbours_occupied) {
+        Some(seat) => println!("Ticket missing is: {}", seat.pass.seat_id()),
+        None => println!("No missing seat has both neighbours occupied"),
+    }
+    Ok(())
 }
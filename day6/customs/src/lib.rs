@@ -0,0 +1,338 @@
+// Each person answers "yes" to some of the questions a to z, so their answers fit in a 26-bit set
+// where bit 0 is question a and bit 25 is question z. A group is the list of the sets of its
+// people, and every puzzle question is some set operation over a group.
+
+pub mod report;
+
+use report::ReportFormat;
+use std::error::Error;
+use std::fmt;
+use std::io::BufRead;
+use std::str::FromStr;
+
+pub const NUM_QUESTIONS: usize = 26;
+
+#[derive(Debug)]
+pub enum CustomsError {
+    InvalidAnswer { line: usize, answer: char },
+    Io(std::io::Error),
+}
+
+impl fmt::Display for CustomsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CustomsError::InvalidAnswer { line, answer } => {
+                write!(
+                    f,
+                    "line {}: '{}' is not a question from a to z",
+                    line, answer
+                )
+            }
+            CustomsError::Io(err) => write!(f, "couldn't read the answers: {}", err),
+        }
+    }
+}
+
+impl Error for CustomsError {}
+
+impl From<std::io::Error> for CustomsError {
+    fn from(err: std::io::Error) -> CustomsError {
+        CustomsError::Io(err)
+    }
+}
+
+/// The questions someone (or some combination of people) answered "yes" to.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Answers(u32);
+
+impl Answers {
+    pub const NONE: Answers = Answers(0);
+    pub const ALL: Answers = Answers((1 << NUM_QUESTIONS) - 1);
+
+    fn bit(question: char) -> Option<u32> {
+        if question.is_ascii_lowercase() {
+            Some(1 << (question as u32 - 'a' as u32))
+        } else {
+            None
+        }
+    }
+
+    pub fn union(self, other: Answers) -> Answers {
+        Answers(self.0 | other.0)
+    }
+
+    pub fn intersection(self, other: Answers) -> Answers {
+        Answers(self.0 & other.0)
+    }
+
+    pub fn symmetric_difference(self, other: Answers) -> Answers {
+        Answers(self.0 ^ other.0)
+    }
+
+    pub fn contains(self, question: char) -> bool {
+        Answers::bit(question).is_some_and(|bit| self.0 & bit != 0)
+    }
+
+    pub fn count(self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    /// The questions in the set, in alphabetical order.
+    pub fn questions(self) -> impl Iterator<Item = char> {
+        (b'a'..=b'z')
+            .map(char::from)
+            .filter(move |&x| self.contains(x))
+    }
+}
+
+impl FromStr for Answers {
+    type Err = char;
+
+    /// Parses the answers of a single person, failing on the first letter that isn't a question.
+    fn from_str(s: &str) -> Result<Answers, char> {
+        s.chars()
+            .try_fold(Answers::NONE, |acc, c| match Answers::bit(c) {
+                Some(bit) => Ok(Answers(acc.0 | bit)),
+                None => Err(c),
+            })
+    }
+}
+
+impl fmt::Display for Answers {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for question in self.questions() {
+            write!(f, "{}", question)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Group {
+    people: Vec<Answers>,
+}
+
+impl Group {
+    pub fn new(people: Vec<Answers>) -> Group {
+        Group { people }
+    }
+
+    pub fn people(&self) -> &[Answers] {
+        &self.people
+    }
+
+    /// Questions anyone in the group answered.
+    pub fn union(&self) -> Answers {
+        self.people
+            .iter()
+            .fold(Answers::NONE, |acc, &x| acc.union(x))
+    }
+
+    /// Questions everyone in the group answered.
+    pub fn intersection(&self) -> Answers {
+        self.people
+            .iter()
+            .fold(Answers::ALL, |acc, &x| acc.intersection(x))
+            .intersection(self.union())
+    }
+
+    /// Questions answered by an odd number of people in the group.
+    pub fn symmetric_difference(&self) -> Answers {
+        self.people
+            .iter()
+            .fold(Answers::NONE, |acc, &x| acc.symmetric_difference(x))
+    }
+
+    /// Questions answered by at least `k` people in the group.
+    pub fn at_least(&self, k: usize) -> Answers {
+        if k == 0 {
+            return Answers::ALL;
+        }
+        if k > self.people.len() {
+            return Answers::NONE;
+        }
+        // at_least[i] holds the questions answered by at least i + 1 of the people seen so far
+        let mut at_least = vec![Answers::NONE; k];
+        for &person in &self.people {
+            for i in (1..k).rev() {
+                at_least[i] = at_least[i].union(at_least[i - 1].intersection(person));
+            }
+            at_least[0] = at_least[0].union(person);
+        }
+        at_least[k - 1]
+    }
+}
+
+/// Which set of questions to count for every group.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Query {
+    Union,
+    Intersection,
+    SymmetricDifference,
+    AtLeast(usize),
+}
+
+impl Query {
+    pub fn apply(self, group: &Group) -> Answers {
+        match self {
+            Query::Union => group.union(),
+            Query::Intersection => group.intersection(),
+            Query::SymmetricDifference => group.symmetric_difference(),
+            Query::AtLeast(k) => group.at_least(k),
+        }
+    }
+}
+
+impl FromStr for Query {
+    type Err = String;
+
+    /// Accepts `union`, `intersection`, `symmetric-difference` and `at-least:K`.
+    fn from_str(s: &str) -> Result<Query, String> {
+        match s {
+            "union" => Ok(Query::Union),
+            "intersection" => Ok(Query::Intersection),
+            "symmetric-difference" => Ok(Query::SymmetricDifference),
+            _ => match s.strip_prefix("at-least:").map(str::parse) {
+                Some(Ok(k)) => Ok(Query::AtLeast(k)),
+                _ => Err(format!(
+                    "unknown query {}, expected union, intersection, symmetric-difference or at-least:K",
+                    s
+                )),
+            },
+        }
+    }
+}
+
+/// What the command line asks for: the query to count, or a survey report instead.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Options {
+    pub query: Query,
+    pub report: Option<ReportFormat>,
+}
+
+impl Options {
+    /// Builds the options from command line arguments: `--query QUERY` and `--report FORMAT`.
+    /// Without `--query`, `default_query` is counted.
+    pub fn from_args<I: Iterator<Item = String>>(
+        default_query: Query,
+        mut args: I,
+    ) -> Result<Options, Box<dyn Error>> {
+        let mut options = Options {
+            query: default_query,
+            report: None,
+        };
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--query" => {
+                    let value = args.next().ok_or("--query needs a value")?;
+                    options.query = value.parse()?;
+                }
+                "--report" => {
+                    let value = args
+                        .next()
+                        .ok_or("--report needs one of: text, csv or json")?;
+                    options.report = Some(value.parse()?);
+                }
+                _ => return Err(format!("unknown argument: {}", arg).into()),
+            }
+        }
+        Ok(options)
+    }
+}
+
+/// Reads groups separated by blank lines, one person per line.
+pub fn parse_groups<R: BufRead>(reader: R) -> Result<Vec<Group>, CustomsError> {
+    let mut groups = Vec::new();
+    let mut people = Vec::new();
+    for (i, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() {
+            if !people.is_empty() {
+                groups.push(Group::new(people));
+                people = Vec::new();
+            }
+            continue;
+        }
+        let answers = line.parse().map_err(|answer| CustomsError::InvalidAnswer {
+            line: i + 1,
+            answer,
+        })?;
+        people.push(answers);
+    }
+    if !people.is_empty() {
+        groups.push(Group::new(people));
+    }
+    Ok(groups)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::io::BufReader;
+
+    fn example_groups() -> Vec<Group> {
+        let file = File::open("../example_input").unwrap();
+        parse_groups(BufReader::new(file)).unwrap()
+    }
+
+    fn total(groups: &[Group], query: Query) -> usize {
+        groups.iter().map(|x| query.apply(x).count()).sum()
+    }
+
+    #[test]
+    fn test_example_input() {
+        let groups = example_groups();
+        assert_eq!(groups.len(), 5);
+        assert_eq!(total(&groups, Query::Union), 11);
+        assert_eq!(total(&groups, Query::Intersection), 6);
+        assert_eq!(total(&groups, Query::AtLeast(1)), 11);
+        assert_eq!(total(&groups, Query::AtLeast(2)), 2);
+    }
+
+    #[test]
+    fn test_group_operations() {
+        let group = Group::new(vec![
+            "abc".parse().unwrap(),
+            "bcd".parse().unwrap(),
+            "cde".parse().unwrap(),
+        ]);
+        assert_eq!(group.union().to_string(), "abcde");
+        assert_eq!(group.intersection().to_string(), "c");
+        assert_eq!(group.symmetric_difference().to_string(), "ace");
+        assert_eq!(group.at_least(2).to_string(), "bcd");
+        assert_eq!(group.at_least(3), group.intersection());
+        assert_eq!(group.at_least(4), Answers::NONE);
+        assert_eq!(group.at_least(100_000_000_000_000), Answers::NONE);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!("abC".parse::<Answers>(), Err('C'));
+        assert!(matches!(
+            parse_groups("ab\n\na1\n".as_bytes()),
+            Err(CustomsError::InvalidAnswer {
+                line: 3,
+                answer: '1'
+            })
+        ));
+        assert_eq!("at-least:3".parse(), Ok(Query::AtLeast(3)));
+        assert!("at-least:x".parse::<Query>().is_err());
+    }
+
+    #[test]
+    fn test_options_from_args() {
+        let args = |args: &[&str]| args.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+        let options =
+            Options::from_args(Query::Union, args(&["--query", "at-least:2"]).into_iter()).unwrap();
+        assert_eq!(options.query, Query::AtLeast(2));
+        assert_eq!(options.report, None);
+        let options =
+            Options::from_args(Query::Union, args(&["--report", "json"]).into_iter()).unwrap();
+        assert_eq!(options.query, Query::Union);
+        assert_eq!(options.report, Some(ReportFormat::Json));
+        assert!(Options::from_args(Query::Union, args(&["--query"]).into_iter()).is_err());
+        assert!(Options::from_args(Query::Union, args(&["--exprot"]).into_iter()).is_err());
+    }
+}
@@ -0,0 +1,310 @@
+use crate::{Answers, Group, NUM_QUESTIONS};
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+const HISTOGRAM_WIDTH: usize = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReportFormat {
+    Text,
+    Csv,
+    Json,
+}
+
+impl FromStr for ReportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<ReportFormat, String> {
+        match s {
+            "text" => Ok(ReportFormat::Text),
+            "csv" => Ok(ReportFormat::Csv),
+            "json" => Ok(ReportFormat::Json),
+            _ => Err(format!("unknown report format: {}", s)),
+        }
+    }
+}
+
+/// How many people answered each question, for a group or for the whole survey.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tally {
+    pub people: usize,
+    pub counts: [usize; NUM_QUESTIONS],
+}
+
+impl Tally {
+    fn new() -> Tally {
+        Tally {
+            people: 0,
+            counts: [0; NUM_QUESTIONS],
+        }
+    }
+
+    fn add(&mut self, other: &Tally) {
+        self.people += other.people;
+        for (count, other_count) in self.counts.iter_mut().zip(other.counts.iter()) {
+            *count += other_count;
+        }
+    }
+
+    /// Questions no one answered.
+    pub fn unanswered(&self) -> Answers {
+        let bits = self
+            .counts
+            .iter()
+            .enumerate()
+            .filter(|(_, &count)| count == 0)
+            .fold(0, |acc, (i, _)| acc | 1 << i);
+        Answers(bits)
+    }
+
+    fn to_json(&self) -> String {
+        let counts: Vec<String> = questions()
+            .zip(self.counts.iter())
+            .map(|(question, count)| format!("\"{}\":{}", question, count))
+            .collect();
+        format!(
+            "{{\"people\":{},\"counts\":{{{}}},\"unanswered\":\"{}\"}}",
+            self.people,
+            counts.join(","),
+            self.unanswered()
+        )
+    }
+}
+
+impl From<&Group> for Tally {
+    fn from(group: &Group) -> Tally {
+        let mut tally = Tally::new();
+        tally.people = group.people().len();
+        for person in group.people() {
+            for question in person.questions() {
+                tally.counts[question as usize - 'a' as usize] += 1;
+            }
+        }
+        tally
+    }
+}
+
+fn questions() -> impl Iterator<Item = char> {
+    (b'a'..=b'z').map(char::from)
+}
+
+/// Per-group and overall statistics of the answers of the survey.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SurveyReport {
+    pub groups: Vec<Tally>,
+    pub total: Tally,
+}
+
+impl SurveyReport {
+    pub fn new(groups: &[Group]) -> SurveyReport {
+        let groups: Vec<Tally> = groups.iter().map(Tally::from).collect();
+        let mut total = Tally::new();
+        for group in &groups {
+            total.add(group);
+        }
+        SurveyReport { groups, total }
+    }
+
+    /// Number of groups for each group size.
+    pub fn group_sizes(&self) -> BTreeMap<usize, usize> {
+        let mut sizes = BTreeMap::new();
+        for group in &self.groups {
+            *sizes.entry(group.people).or_insert(0) += 1;
+        }
+        sizes
+    }
+
+    /// Writes the report in `format`. Every format has, for each group and for the whole survey,
+    /// the number of people, how many of them answered each question and the questions no one
+    /// answered, followed by the number of groups of every size.
+    pub fn write<W: Write>(&self, out: &mut W, format: ReportFormat) -> io::Result<()> {
+        match format {
+            ReportFormat::Text => self.write_text(out),
+            ReportFormat::Csv => self.write_csv(out),
+            ReportFormat::Json => self.write_json(out),
+        }
+    }
+
+    fn write_text<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        writeln!(
+            out,
+            "{} groups, {} people",
+            self.groups.len(),
+            self.total.people
+        )?;
+        for (name, tally) in self.rows() {
+            writeln!(out)?;
+            match name {
+                Some(i) => writeln!(out, "Group {}: {} people", i, tally.people)?,
+                None => writeln!(out, "Overall: {} people", tally.people)?,
+            }
+            let answered = questions()
+                .zip(tally.counts.iter().copied())
+                .filter(|&(_, count)| count > 0)
+                .map(|(question, count)| (question.to_string(), count));
+            write_histogram(out, answered)?;
+            writeln!(out, "No one answered: {}", tally.unanswered())?;
+        }
+        writeln!(out)?;
+        writeln!(out, "Groups per size:")?;
+        let sizes = self
+            .group_sizes()
+            .into_iter()
+            .map(|(size, groups)| (format!("{} people", size), groups));
+        write_histogram(out, sizes)
+    }
+
+    /// Writes one table with a row per group and a last `total` row, then, after a blank line, a
+    /// second table with the number of groups of every size.
+    fn write_csv<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        let header: Vec<String> = questions().map(|x| x.to_string()).collect();
+        writeln!(out, "group,people,{},unanswered", header.join(","))?;
+        for (name, tally) in self.rows() {
+            let counts: Vec<String> = tally.counts.iter().map(|x| x.to_string()).collect();
+            writeln!(
+                out,
+                "{},{},{},{}",
+                name.map_or("total".to_string(), |x| x.to_string()),
+                tally.people,
+                counts.join(","),
+                tally.unanswered()
+            )?;
+        }
+        writeln!(out)?;
+        writeln!(out, "people,groups")?;
+        for (size, groups) in self.group_sizes() {
+            writeln!(out, "{},{}", size, groups)?;
+        }
+        Ok(())
+    }
+
+    fn write_json<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        let groups: Vec<String> = self.groups.iter().map(Tally::to_json).collect();
+        let sizes: Vec<String> = self
+            .group_sizes()
+            .iter()
+            .map(|(size, groups)| format!("\"{}\":{}", size, groups))
+            .collect();
+        writeln!(
+            out,
+            "{{\"groups\":[{}],\"total\":{},\"group_sizes\":{{{}}}}}",
+            groups.join(","),
+            self.total.to_json(),
+            sizes.join(",")
+        )
+    }
+
+    /// Every group, numbered from 1, followed by the total.
+    fn rows(&self) -> impl Iterator<Item = (Option<usize>, &Tally)> {
+        self.groups
+            .iter()
+            .enumerate()
+            .map(|(i, x)| (Some(i + 1), x))
+            .chain(std::iter::once((None, &self.total)))
+    }
+}
+
+/// Draws one bar per row, scaled so the longest one is `HISTOGRAM_WIDTH` characters.
+fn write_histogram<W: Write, I: Iterator<Item = (String, usize)>>(
+    out: &mut W,
+    rows: I,
+) -> io::Result<()> {
+    let rows: Vec<(String, usize)> = rows.collect();
+    let label_width = rows.iter().map(|(x, _)| x.len()).max().unwrap_or(0);
+    let max = rows.iter().map(|&(_, x)| x).max().unwrap_or(0).max(1);
+    for (label, count) in rows {
+        let bar = "#".repeat((count * HISTOGRAM_WIDTH).div_ceil(max));
+        writeln!(
+            out,
+            "{:>label_width$} | {:<width$} {}",
+            label,
+            bar,
+            count,
+            label_width = label_width,
+            width = HISTOGRAM_WIDTH
+        )?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse_groups;
+
+    fn example_report() -> SurveyReport {
+        let groups =
+            parse_groups("abc\n\na\nb\nc\n\nab\nac\n\na\na\na\na\n\nb\n".as_bytes()).unwrap();
+        SurveyReport::new(&groups)
+    }
+
+    #[test]
+    fn test_report() {
+        let report = example_report();
+        assert_eq!(report.groups.len(), 5);
+        assert_eq!(report.total.people, 11);
+        assert_eq!(&report.total.counts[..4], &[8, 4, 3, 0]);
+        assert_eq!(report.groups[2].unanswered().count(), NUM_QUESTIONS - 3);
+        assert_eq!(
+            report.total.unanswered().to_string(),
+            "defghijklmnopqrstuvwxyz"
+        );
+        let sizes: Vec<(usize, usize)> = report.group_sizes().into_iter().collect();
+        assert_eq!(sizes, vec![(1, 2), (2, 1), (3, 1), (4, 1)]);
+    }
+
+    #[test]
+    fn test_report_csv() {
+        let mut out = Vec::new();
+        example_report().write(&mut out, ReportFormat::Csv).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 13);
+        assert!(lines[0].starts_with("group,people,a,b,c,d,"));
+        assert!(lines[1].starts_with("1,1,1,1,1,0,"));
+        assert!(lines[6].starts_with("total,11,8,4,3,0,"));
+        assert!(lines[6].ends_with(",defghijklmnopqrstuvwxyz"));
+        assert_eq!(
+            &lines[7..],
+            &["", "people,groups", "1,2", "2,1", "3,1", "4,1"]
+        );
+    }
+
+    #[test]
+    fn test_report_text() {
+        let mut out = Vec::new();
+        example_report()
+            .write(&mut out, ReportFormat::Text)
+            .unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let expected_group = "\
+Group 3: 2 people
+a | ################################################## 2
+b | #########################                          1
+c | #########################                          1
+No one answered: defghijklmnopqrstuvwxyz
+";
+        assert!(out.starts_with("5 groups, 11 people\n"));
+        assert!(out.contains(expected_group));
+        assert!(out.contains("Overall: 11 people\na | "));
+        assert!(out.contains("\nGroups per size:\n1 people | "));
+        assert!(
+            out.ends_with("\n4 people | #########################                          1\n")
+        );
+    }
+
+    #[test]
+    fn test_report_json() {
+        let mut out = Vec::new();
+        example_report()
+            .write(&mut out, ReportFormat::Json)
+            .unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.starts_with("{\"groups\":[{\"people\":1,\"counts\":{\"a\":1,\"b\":1,"));
+        assert!(
+            out.contains("\"total\":{\"people\":11,\"counts\":{\"a\":8,\"b\":4,\"c\":3,\"d\":0,")
+        );
+        assert!(out.ends_with(",\"group_sizes\":{\"1\":2,\"2\":1,\"3\":1,\"4\":1}}\n"));
+    }
+}
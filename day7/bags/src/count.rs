@@ -0,0 +1,11 @@
+// Every bag holds its contents' counts times the number of each, so the total grows exponentially
+// with the depth of the rules. Counting needs zero, one, a conversion from the numbers in the
+// rules and additions and multiplications that notice when they overflow; num-traits has those
+// for every integer type and num-bigint implements them for `BigUint`, which never overflows.
+
+use num_traits::{CheckedAdd, CheckedMul, FromPrimitive, One, Zero};
+
+/// A number type bags can be counted with.
+pub trait BagCount: Clone + Zero + One + CheckedAdd + CheckedMul + FromPrimitive {}
+
+impl<T: Clone + Zero + One + CheckedAdd + CheckedMul + FromPrimitive> BagCount for T {}
@@ -0,0 +1,179 @@
+use crate::{BagGraph, BagId};
+use std::collections::HashSet;
+use std::io::{self, Write};
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    Dot,
+    Mermaid,
+}
+
+impl FromStr for ExportFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<ExportFormat, String> {
+        match s {
+            "dot" => Ok(ExportFormat::Dot),
+            "mermaid" => Ok(ExportFormat::Mermaid),
+            _ => Err(format!("unknown export format: {}", s)),
+        }
+    }
+}
+
+/// Which part of the graph to export.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Scope {
+    All,
+    /// The bag and every bag it eventually contains.
+    From(BagId),
+    /// The bag and every bag that can eventually contain it.
+    To(BagId),
+}
+
+fn quote(name: &str) -> String {
+    format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Mermaid labels have no backslash escapes, quotes are written as an entity instead.
+fn mermaid_quote(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "#quot;"))
+}
+
+impl BagGraph {
+    fn scope_ids(&self, scope: Scope) -> Vec<BagId> {
+        let (start, mut ids) = match scope {
+            Scope::All => return self.ids().collect(),
+            Scope::From(id) => (id, self.descendants(id)),
+            Scope::To(id) => (id, self.ancestors(id)),
+        };
+        if !ids.contains(&start) {
+            ids.push(start);
+        }
+        ids.sort_unstable();
+        ids
+    }
+
+    /// Writes the bags in `scope` and the rules between them as a Graphviz or Mermaid graph, with
+    /// the number of bags on every edge.
+    pub fn export<W: Write>(
+        &self,
+        out: &mut W,
+        format: ExportFormat,
+        scope: Scope,
+    ) -> io::Result<()> {
+        let ids = self.scope_ids(scope);
+        let included: HashSet<BagId> = ids.iter().copied().collect();
+        let edges = ids.iter().flat_map(|&from| {
+            self.contains(from)
+                .iter()
+                .filter(|(to, _)| included.contains(to))
+                .map(move |&(to, number)| (from, to, number))
+        });
+        match format {
+            ExportFormat::Dot => {
+                writeln!(out, "digraph bags {{")?;
+                for &id in &ids {
+                    writeln!(out, "    {};", quote(self.name(id)))?;
+                }
+                for (from, to, number) in edges {
+                    writeln!(
+                        out,
+                        "    {} -> {} [label=\"{}\"];",
+                        quote(self.name(from)),
+                        quote(self.name(to)),
+                        number
+                    )?;
+                }
+                writeln!(out, "}}")
+            }
+            ExportFormat::Mermaid => {
+                writeln!(out, "graph LR")?;
+                for &id in &ids {
+                    writeln!(out, "    n{}[{}]", id.0, mermaid_quote(self.name(id)))?;
+                }
+                for (from, to, number) in edges {
+                    writeln!(out, "    n{} -->|{}| n{}", from.0, number, to.0)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph() -> BagGraph {
+        let mut graph = BagGraph::new();
+        for rule in [
+            "bright white bags contain 1 shiny gold bag.",
+            "muted yellow bags contain 2 shiny gold bags, 9 faded blue bags.",
+            "shiny gold bags contain 1 dark olive bag.",
+            "faded blue bags contain no other bags.",
+            "dark olive bags contain no other bags.",
+        ]
+        .iter()
+        {
+            graph.add_rule(rule).unwrap();
+        }
+        graph
+    }
+
+    fn export(graph: &BagGraph, format: ExportFormat, scope: Scope) -> String {
+        let mut out = Vec::new();
+        graph.export(&mut out, format, scope).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_export_dot_from() {
+        let graph = graph();
+        let scope = Scope::From(graph.id("muted yellow").unwrap());
+        let expected = "digraph bags {
+    \"shiny gold\";
+    \"muted yellow\";
+    \"faded blue\";
+    \"dark olive\";
+    \"shiny gold\" -> \"dark olive\" [label=\"1\"];
+    \"muted yellow\" -> \"shiny gold\" [label=\"2\"];
+    \"muted yellow\" -> \"faded blue\" [label=\"9\"];
+}
+";
+        assert_eq!(export(&graph, ExportFormat::Dot, scope), expected);
+    }
+
+    #[test]
+    fn test_export_mermaid_to() {
+        let graph = graph();
+        let scope = Scope::To(graph.id("shiny gold").unwrap());
+        let expected = "graph LR
+    n0[\"bright white\"]
+    n1[\"shiny gold\"]
+    n2[\"muted yellow\"]
+    n0 -->|1| n1
+    n2 -->|2| n1
+";
+        assert_eq!(export(&graph, ExportFormat::Mermaid, scope), expected);
+        assert_eq!(
+            export(&graph, ExportFormat::Mermaid, Scope::All)
+                .lines()
+                .count(),
+            1 + 5 + 4
+        );
+    }
+
+    #[test]
+    fn test_export_quotes() {
+        let mut graph = BagGraph::new();
+        graph
+            .add_rule("dark \"red\" bags contain 2 light\\blue bags.")
+            .unwrap();
+        let dot = export(&graph, ExportFormat::Dot, Scope::All);
+        assert!(dot.contains("    \"dark \\\"red\\\"\" -> \"light\\\\blue\" [label=\"2\"];\n"));
+        let mermaid = export(&graph, ExportFormat::Mermaid, Scope::All);
+        assert!(mermaid.contains("    n0[\"dark #quot;red#quot;\"]\n"));
+        assert!(mermaid.contains("    n1[\"light\\blue\"]\n"));
+    }
+}
@@ -0,0 +1,590 @@
+// The rules form a directed graph: there is an edge from bag A to bag B with weight n when the rule
+// for A says it contains n B bags. Every colour is interned to a `BagId` the first time it's seen,
+// either as a container or as a content, and both edge directions are stored so that "what does
+// A contain" and "what can contain B" are both a lookup.
+
+pub mod count;
+pub mod export;
+
+use count::BagCount;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::error::Error;
+use std::fmt;
+use std::io::BufRead;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct BagId(usize);
+
+#[derive(Debug, PartialEq)]
+pub enum BagError {
+    InvalidRule(String),
+    UnknownBag(String),
+    UndefinedBag(String),
+    DuplicateRule(String),
+    SelfContainment(String),
+    Cycle(Vec<String>),
+    Overflow(String),
+    Io(String),
+}
+
+impl fmt::Display for BagError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BagError::InvalidRule(rule) => write!(f, "invalid rule: {}", rule),
+            BagError::UnknownBag(name) => write!(f, "no bag is named {}", name),
+            BagError::UndefinedBag(name) => write!(f, "there is no rule for {} bags", name),
+            BagError::DuplicateRule(name) => {
+                write!(f, "there is more than one rule for {} bags", name)
+            }
+            BagError::SelfContainment(name) => write!(f, "{} bags contain themselves", name),
+            BagError::Cycle(names) => write!(f, "bags contain each other: {}", names.join(" -> ")),
+            BagError::Overflow(name) => {
+                write!(f, "the number of bags inside {} bags overflowed", name)
+            }
+            BagError::Io(err) => write!(f, "couldn't read the rules: {}", err),
+        }
+    }
+}
+
+impl Error for BagError {}
+
+#[derive(Debug, Default)]
+pub struct BagGraph {
+    names: Vec<String>,
+    ids: HashMap<String, BagId>,
+    has_rule: Vec<bool>,
+    duplicates: Vec<BagId>,
+    contains: Vec<Vec<(BagId, u64)>>,
+    contained_by: Vec<Vec<(BagId, u64)>>,
+}
+
+impl BagGraph {
+    pub fn new() -> BagGraph {
+        Default::default()
+    }
+
+    fn intern(&mut self, name: &str) -> BagId {
+        if let Some(&id) = self.ids.get(name) {
+            return id;
+        }
+        let id = BagId(self.names.len());
+        self.names.push(name.to_string());
+        self.ids.insert(name.to_string(), id);
+        self.has_rule.push(false);
+        self.contains.push(Vec::new());
+        self.contained_by.push(Vec::new());
+        id
+    }
+
+    /// Reads one rule per line, skipping blank lines.
+    pub fn from_reader<R: BufRead>(reader: R) -> Result<BagGraph, BagError> {
+        let mut graph = BagGraph::new();
+        for line in reader.lines() {
+            let line = line.map_err(|err| BagError::Io(err.to_string()))?;
+            if !line.trim().is_empty() {
+                graph.add_rule(&line)?;
+            }
+        }
+        Ok(graph)
+    }
+
+    /// Adds a rule like `light red bags contain 1 bright white bag, 2 muted yellow bags.` When a bag
+    /// already has a rule, the new one is only remembered as a duplicate for `validate`.
+    pub fn add_rule(&mut self, rule: &str) -> Result<BagId, BagError> {
+        let invalid = || BagError::InvalidRule(rule.to_string());
+        let mut iter = rule.trim().splitn(2, " contain ");
+        let container = iter
+            .next()
+            .and_then(|x| x.strip_suffix(" bags"))
+            .ok_or_else(invalid)?;
+        let contents = iter
+            .next()
+            .and_then(|x| x.strip_suffix('.'))
+            .ok_or_else(invalid)?;
+
+        let mut parsed_contents = Vec::new();
+        if contents != "no other bags" {
+            for content in contents.split(", ") {
+                let content = content
+                    .strip_suffix(" bags")
+                    .or_else(|| content.strip_suffix(" bag"))
+                    .ok_or_else(invalid)?;
+                let mut content_iter = content.splitn(2, ' ');
+                let number = content_iter
+                    .next()
+                    .and_then(|x| x.parse::<u64>().ok())
+                    .ok_or_else(invalid)?;
+                let name = content_iter.next().ok_or_else(invalid)?;
+                parsed_contents.push((name, number));
+            }
+        }
+
+        let container = self.intern(container);
+        if self.has_rule[container.0] {
+            self.duplicates.push(container);
+            return Ok(container);
+        }
+        self.has_rule[container.0] = true;
+        for (name, number) in parsed_contents {
+            let content = self.intern(name);
+            self.contains[container.0].push((content, number));
+            self.contained_by[content.0].push((container, number));
+        }
+        Ok(container)
+    }
+
+    pub fn id(&self, name: &str) -> Result<BagId, BagError> {
+        self.ids
+            .get(name)
+            .copied()
+            .ok_or_else(|| BagError::UnknownBag(name.to_string()))
+    }
+
+    pub fn name(&self, id: BagId) -> &str {
+        &self.names[id.0]
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    pub fn ids(&self) -> impl Iterator<Item = BagId> {
+        (0..self.names.len()).map(BagId)
+    }
+
+    /// Whether a rule for this bag was given, as opposed to the bag only being mentioned as the
+    /// content of another bag.
+    pub fn has_rule(&self, id: BagId) -> bool {
+        self.has_rule[id.0]
+    }
+
+    /// The bags directly inside `id` and how many of each.
+    pub fn contains(&self, id: BagId) -> &[(BagId, u64)] {
+        &self.contains[id.0]
+    }
+
+    /// The bags that directly hold `id` and how many of it each one holds.
+    pub fn contained_by(&self, id: BagId) -> &[(BagId, u64)] {
+        &self.contained_by[id.0]
+    }
+
+    /// Every bag that can eventually contain `id`, nearest first. Each bag is visited once, so
+    /// this also terminates when the rules have cycles.
+    pub fn ancestors(&self, id: BagId) -> Vec<BagId> {
+        self.reachable(id, BagGraph::contained_by)
+    }
+
+    /// Every bag that `id` eventually contains, nearest first. Like `ancestors`, this terminates
+    /// when the rules have cycles.
+    pub fn descendants(&self, id: BagId) -> Vec<BagId> {
+        self.reachable(id, BagGraph::contains)
+    }
+
+    /// Breadth-first search from `id`, following the edges that `edges` gives for every bag.
+    fn reachable(&self, id: BagId, edges: fn(&BagGraph, BagId) -> &[(BagId, u64)]) -> Vec<BagId> {
+        let mut visited: HashSet<BagId> = HashSet::new();
+        let mut queue: VecDeque<BagId> = VecDeque::new();
+        let mut reachable = Vec::new();
+        queue.push_back(id);
+        while let Some(bag) = queue.pop_front() {
+            for &(next, _) in edges(self, bag) {
+                if visited.insert(next) {
+                    reachable.push(next);
+                    queue.push_back(next);
+                }
+            }
+        }
+        reachable
+    }
+
+    fn cycle_error(&self, stack: &[(BagId, usize)], repeated: BagId) -> BagError {
+        let start = stack.iter().position(|&(x, _)| x == repeated).unwrap_or(0);
+        let mut names: Vec<String> = stack[start..]
+            .iter()
+            .map(|&(x, _)| self.name(x).to_string())
+            .collect();
+        if names.len() == 1 {
+            return BagError::SelfContainment(names.remove(0));
+        }
+        names.push(self.name(repeated).to_string());
+        BagError::Cycle(names)
+    }
+
+    /// Checks the rules for bags that are mentioned but have no rule, bags with more than one rule,
+    /// bags that contain themselves and groups of bags that contain each other.
+    pub fn validate(&self) -> Vec<BagError> {
+        let mut problems: Vec<BagError> = self
+            .ids()
+            .filter(|&x| !self.has_rule(x))
+            .map(|x| BagError::UndefinedBag(self.name(x).to_string()))
+            .collect();
+        problems.extend(
+            self.duplicates
+                .iter()
+                .map(|&x| BagError::DuplicateRule(self.name(x).to_string())),
+        );
+
+        // Depth first search from every bag, where a bag still on the stack being reached again
+        // closes a cycle
+        let mut visited = vec![false; self.len()];
+        let mut on_stack = vec![false; self.len()];
+        for root in self.ids() {
+            if visited[root.0] {
+                continue;
+            }
+            visited[root.0] = true;
+            on_stack[root.0] = true;
+            let mut stack = vec![(root, 0)];
+            while let Some((bag, next)) = stack.last_mut() {
+                if let Some(&(content, _)) = self.contains(*bag).get(*next) {
+                    *next += 1;
+                    if on_stack[content.0] {
+                        problems.push(self.cycle_error(&stack, content));
+                    } else if !visited[content.0] {
+                        visited[content.0] = true;
+                        on_stack[content.0] = true;
+                        stack.push((content, 0));
+                    }
+                } else {
+                    on_stack[bag.0] = false;
+                    stack.pop();
+                }
+            }
+        }
+        problems
+    }
+
+    /// Total number of bags inside `id`, counted with any `BagCount` type. Every bag is only
+    /// counted once and the search doesn't recurse, so deep rules can't overflow the stack; rules
+    /// with cycles or missing bags are an error, and so is a count that doesn't fit in `T`.
+    pub fn count_inside<T: BagCount>(&self, id: BagId) -> Result<T, BagError> {
+        let mut inside: Vec<Option<T>> = vec![None; self.len()];
+        let mut on_stack = vec![false; self.len()];
+        let mut stack = vec![(id, 0)];
+        on_stack[id.0] = true;
+        while let Some((bag, next)) = stack.last_mut() {
+            let bag = *bag;
+            if !self.has_rule(bag) {
+                return Err(BagError::UndefinedBag(self.name(bag).to_string()));
+            }
+            if let Some(&(content, _)) = self.contains(bag).get(*next) {
+                *next += 1;
+                if on_stack[content.0] {
+                    return Err(self.cycle_error(&stack, content));
+                }
+                if inside[content.0].is_none() {
+                    on_stack[content.0] = true;
+                    stack.push((content, 0));
+                }
+            } else {
+                let total = self
+                    .contains(bag)
+                    .iter()
+                    .try_fold(T::zero(), |acc, &(content, number)| {
+                        let number = T::from_u64(number)?;
+                        let content_inside = inside[content.0]
+                            .as_ref()
+                            .expect("contents are counted before the bag holding them");
+                        let each = T::one().checked_add(content_inside)?;
+                        acc.checked_add(&number.checked_mul(&each)?)
+                    })
+                    .ok_or_else(|| BagError::Overflow(self.name(bag).to_string()))?;
+                inside[bag.0] = Some(total);
+                on_stack[bag.0] = false;
+                stack.pop();
+            }
+        }
+        Ok(inside[id.0]
+            .take()
+            .expect("the starting bag is counted when the stack empties"))
+    }
+}
+
+impl FromStr for BagGraph {
+    type Err = BagError;
+
+    fn from_str(s: &str) -> Result<BagGraph, BagError> {
+        BagGraph::from_reader(s.as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::io::BufReader;
+
+    fn example(path: &str) -> BagGraph {
+        BagGraph::from_reader(BufReader::new(File::open(path).unwrap())).unwrap()
+    }
+
+    #[test]
+    fn test_example_input() {
+        let graph = example("../example_input");
+        let shiny_gold = graph.id("shiny gold").unwrap();
+        assert_eq!(graph.len(), 9);
+        assert_eq!(graph.ancestors(shiny_gold).len(), 4);
+        assert_eq!(graph.count_inside::<u64>(shiny_gold), Ok(32));
+        assert!(graph.validate().is_empty());
+    }
+
+    #[test]
+    fn test_example_input_2() {
+        let graph = example("../example_input_2");
+        let shiny_gold = graph.id("shiny gold").unwrap();
+        assert_eq!(graph.ancestors(shiny_gold).len(), 0);
+        assert_eq!(graph.count_inside::<u64>(shiny_gold), Ok(126));
+    }
+
+    #[test]
+    fn test_from_str() {
+        let graph: BagGraph = "faded blue bags contain no other bags.\n\n\
+                               shiny gold bags contain 3 faded blue bags.\n"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            graph.count_inside::<u64>(graph.id("shiny gold").unwrap()),
+            Ok(3)
+        );
+        assert_eq!(
+            "shiny gold bags contain faded blue bags."
+                .parse::<BagGraph>()
+                .err(),
+            Some(BagError::InvalidRule(
+                "shiny gold bags contain faded blue bags.".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_add_rule() {
+        let mut graph = BagGraph::new();
+        let light_red = graph
+            .add_rule("light red bags contain 1 bright white bag, 2 muted yellow bags.")
+            .unwrap();
+        let faded_blue = graph
+            .add_rule("faded blue bags contain no other bags.")
+            .unwrap();
+        let bright_white = graph.id("bright white").unwrap();
+        let muted_yellow = graph.id("muted yellow").unwrap();
+
+        assert_eq!(graph.name(light_red), "light red");
+        assert_eq!(graph.len(), 4);
+        assert_eq!(
+            graph.contains(light_red),
+            &[(bright_white, 1), (muted_yellow, 2)]
+        );
+        assert_eq!(graph.contained_by(muted_yellow), &[(light_red, 2)]);
+        assert!(graph.contains(faded_blue).is_empty());
+        assert!(graph.has_rule(light_red));
+        assert!(!graph.has_rule(bright_white));
+        assert_eq!(
+            graph.id("shiny gold"),
+            Err(BagError::UnknownBag("shiny gold".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_add_invalid_rule() {
+        let mut graph = BagGraph::new();
+        for rule in [
+            "light red bags hold 1 bright white bag.",
+            "light red bags contain one bright white bag.",
+            "light red bags contain 1 bright white bag",
+        ]
+        .iter()
+        {
+            assert_eq!(
+                graph.add_rule(rule),
+                Err(BagError::InvalidRule(rule.to_string()))
+            );
+        }
+        assert!(graph.is_empty());
+    }
+
+    #[test]
+    fn test_ancestors() {
+        let mut graph = BagGraph::new();
+        for rule in [
+            "light red bags contain 1 bright white bag, 2 muted yellow bags.",
+            "dark orange bags contain 3 bright white bags, 4 muted yellow bags.",
+            "bright white bags contain 1 shiny gold bag.",
+            "muted yellow bags contain 2 shiny gold bags, 9 faded blue bags.",
+            "shiny gold bags contain 1 dark olive bag, 2 vibrant plum bags.",
+        ]
+        .iter()
+        {
+            graph.add_rule(rule).unwrap();
+        }
+        let mut ancestors: Vec<&str> = graph
+            .ancestors(graph.id("shiny gold").unwrap())
+            .into_iter()
+            .map(|x| graph.name(x))
+            .collect();
+        ancestors.sort_unstable();
+        assert_eq!(
+            ancestors,
+            vec!["bright white", "dark orange", "light red", "muted yellow"]
+        );
+        assert!(graph.ancestors(graph.id("light red").unwrap()).is_empty());
+    }
+
+    #[test]
+    fn test_ancestors_with_cycle() {
+        let mut graph = BagGraph::new();
+        graph
+            .add_rule("dark red bags contain 1 dark blue bag.")
+            .unwrap();
+        graph
+            .add_rule("dark blue bags contain 2 dark red bags.")
+            .unwrap();
+        graph
+            .add_rule("light red bags contain 1 dark red bag.")
+            .unwrap();
+        let dark_red = graph.id("dark red").unwrap();
+        let mut ancestors = graph.ancestors(dark_red);
+        ancestors.sort_unstable();
+        assert_eq!(
+            ancestors,
+            vec![
+                dark_red,
+                graph.id("dark blue").unwrap(),
+                graph.id("light red").unwrap()
+            ]
+        );
+    }
+
+    fn graph(rules: &[&str]) -> BagGraph {
+        let mut graph = BagGraph::new();
+        for rule in rules {
+            graph.add_rule(rule).unwrap();
+        }
+        graph
+    }
+
+    #[test]
+    fn test_count_inside() {
+        let graph = graph(&[
+            "shiny gold bags contain 2 dark red bags.",
+            "dark red bags contain 2 dark orange bags.",
+            "dark orange bags contain 2 dark yellow bags.",
+            "dark yellow bags contain 2 dark green bags.",
+            "dark green bags contain 2 dark blue bags.",
+            "dark blue bags contain 2 dark violet bags.",
+            "dark violet bags contain no other bags.",
+        ]);
+        assert_eq!(
+            graph.count_inside::<u64>(graph.id("shiny gold").unwrap()),
+            Ok(126)
+        );
+        assert_eq!(
+            graph.count_inside::<u64>(graph.id("dark violet").unwrap()),
+            Ok(0)
+        );
+        assert!(graph.validate().is_empty());
+    }
+
+    #[test]
+    fn test_validate() {
+        let graph = graph(&[
+            "light red bags contain 1 dark red bag, 1 light red bag.",
+            "dark red bags contain 1 dark blue bag.",
+            "dark blue bags contain 2 dark red bags, 3 faded blue bags.",
+            "dark blue bags contain no other bags.",
+        ]);
+        assert_eq!(
+            graph.validate(),
+            vec![
+                BagError::UndefinedBag("faded blue".to_string()),
+                BagError::DuplicateRule("dark blue".to_string()),
+                BagError::Cycle(vec![
+                    "dark red".to_string(),
+                    "dark blue".to_string(),
+                    "dark red".to_string()
+                ]),
+                BagError::SelfContainment("light red".to_string()),
+            ]
+        );
+        assert_eq!(
+            graph.count_inside::<u64>(graph.id("dark red").unwrap()),
+            Err(BagError::Cycle(vec![
+                "dark red".to_string(),
+                "dark blue".to_string(),
+                "dark red".to_string()
+            ]))
+        );
+        assert_eq!(
+            graph.count_inside::<u64>(graph.id("faded blue").unwrap()),
+            Err(BagError::UndefinedBag("faded blue".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_count_inside_deep_rules() {
+        let mut graph = BagGraph::new();
+        for i in 0..100_000 {
+            graph
+                .add_rule(&format!("bag{} bags contain 1 bag{} bag.", i, i + 1))
+                .unwrap();
+        }
+        graph
+            .add_rule("bag100000 bags contain no other bags.")
+            .unwrap();
+        assert_eq!(
+            graph.count_inside::<u64>(graph.id("bag0").unwrap()),
+            Ok(100_000)
+        );
+    }
+
+    fn deep_graph(depth: usize, number: u64) -> BagGraph {
+        let mut graph = BagGraph::new();
+        for i in 0..depth {
+            graph
+                .add_rule(&format!(
+                    "bag{} bags contain {} bag{} bags.",
+                    i,
+                    number,
+                    i + 1
+                ))
+                .unwrap();
+        }
+        graph
+            .add_rule(&format!("bag{} bags contain no other bags.", depth))
+            .unwrap();
+        graph
+    }
+
+    #[test]
+    fn test_count_inside_overflow() {
+        let graph = deep_graph(8, 1000);
+        let bag0 = graph.id("bag0").unwrap();
+        assert_eq!(
+            graph.count_inside::<u64>(bag0),
+            Err(BagError::Overflow("bag1".to_string()))
+        );
+        assert_eq!(
+            graph.count_inside::<u128>(bag0),
+            Ok(1_001_001_001_001_001_001_001_000)
+        );
+        assert_eq!(
+            graph.count_inside::<u8>(graph.id("bag7").unwrap()),
+            Err(BagError::Overflow("bag7".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_count_inside_bigint() {
+        use num_bigint::BigUint;
+
+        let graph = deep_graph(30, 1000);
+        let count = graph
+            .count_inside::<BigUint>(graph.id("bag0").unwrap())
+            .unwrap();
+        assert_eq!(count.to_string(), format!("1{}000", "001".repeat(29)));
+    }
+}
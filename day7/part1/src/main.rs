@@ -1,113 +1,66 @@
-use std::collections::HashMap;
-use std::collections::HashSet;
-use std::hash::{Hash, Hasher};
+use bags::export::{ExportFormat, Scope};
+use bags::{BagError, BagGraph};
+use std::env;
+use std::error::Error;
 use std::io;
-use std::io::prelude::*;
 
-const BAG: &str = "shinygold";
+const BAG: &str = "shiny gold";
 
-#[derive(Debug)]
-struct Bags {
-    bags: HashSet<Bag>,
+enum ScopeArg {
+    All,
+    From(String),
+    To(String),
 }
 
-impl Bags {
-    fn new(bags: HashSet<Bag>) -> Bags {
-        Bags { bags }
-    }
-}
-
-#[derive(Debug, Eq)]
-struct Bag {
-    name: String,
-    bags_and_numbers: HashSet<(String, i64)>,
-}
-
-impl Bag {
-    fn new(bag_name: String) -> Bag {
-        Bag {
-            name: bag_name,
-            bags_and_numbers: HashSet::new(),
-        }
-    }
-
-    fn can_contain_bag(&self, bag: &str) -> bool {
-        self.bags_and_numbers.iter().any(|(x, _)| x == bag)
-    }
-
-    fn add_contained_bag(&mut self, bag_and_number: (String, i64)) {
-        self.bags_and_numbers.insert(bag_and_number);
-    }
+struct Args {
+    bag_to_hold: String,
+    export: Option<(ExportFormat, ScopeArg)>,
 }
 
-impl PartialEq for Bag {
-    fn eq(&self, other: &Bag) -> bool {
-        self.name == other.name
-    }
-}
-
-impl Hash for Bag {
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        self.name.hash(state);
-    }
-}
-
-fn is_capable_of_holding(
-    bags: &HashMap<String, Bag>,
-    bag: &Bag,
-    name_of_bag_to_hold: &str,
-) -> bool {
-    if bag.can_contain_bag(name_of_bag_to_hold) {
-        return true;
-    }
-    let mut stack = vec![];
-    stack.push(bag);
-    while !stack.is_empty() {
-        let b = stack.pop().unwrap();
-        if b.can_contain_bag(name_of_bag_to_hold) {
-            return true;
-        } else {
-            b.bags_and_numbers
-                .iter()
-                .for_each(|(x, _)| stack.push(bags.get(x).unwrap()));
+fn parse_args() -> Result<Args, Box<dyn Error>> {
+    let mut bag_to_hold = BAG.to_string();
+    let mut format = None;
+    let mut scope = ScopeArg::All;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--export" => {
+                let value = args.next().ok_or("--export needs one of: dot or mermaid")?;
+                format = Some(value.parse()?);
+            }
+            "--from" => scope = ScopeArg::From(args.next().ok_or("--from needs a colour")?),
+            "--to" => scope = ScopeArg::To(args.next().ok_or("--to needs a colour")?),
+            _ if arg.starts_with("--") => return Err(format!("unknown argument: {}", arg).into()),
+            _ => bag_to_hold = arg,
         }
     }
-    false
+    Ok(Args {
+        bag_to_hold,
+        export: format.map(|format| (format, scope)),
+    })
 }
 
-fn part1(bags: &HashMap<String, Bag>) {
-    let mut cache: HashSet<&str> = HashSet::new();
-    for (name, bag) in bags {
-        if is_capable_of_holding(bags, bag, BAG) {
-            cache.insert(name);
-        }
-    }
-    println!("part1 count: {}", cache.iter().count());
+fn part1(bags: &BagGraph, bag_to_hold: &str) -> Result<(), BagError> {
+    let ancestors = bags.ancestors(bags.id(bag_to_hold)?);
+    println!("part1 count: {}", ancestors.len());
+    Ok(())
 }
 
-fn main() {
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = parse_args()?;
     let stdin = io::stdin();
-    let mut bags: HashMap<String, Bag> = HashMap::new();
-    while let Some(Ok(line)) = stdin.lock().lines().next() {
-        let mut iter = line.split("contain");
-        let bag_name: String = iter
-            .next()
-            .unwrap()
-            .trim()
-            .trim_end_matches("bags")
-            .trim()
-            .split_whitespace()
-            .collect();
-        let iter_bags = iter.next().unwrap().split(',');
-        let mut bag = Bag::new(bag_name.clone());
-        for bag_contained in iter_bags {
-            let mut bag_container_iter = bag_contained.trim().split_whitespace();
-            if let Ok(number) = bag_container_iter.next().unwrap().parse::<i64>() {
-                let bag_contained_name: String = bag_container_iter.take(2).collect();
-                bag.add_contained_bag((bag_contained_name, number));
-            }
+    let bags = BagGraph::from_reader(stdin.lock())?;
+    match args.export {
+        Some((format, scope)) => {
+            let scope = match scope {
+                ScopeArg::All => Scope::All,
+                ScopeArg::From(name) => Scope::From(bags.id(&name)?),
+                ScopeArg::To(name) => Scope::To(bags.id(&name)?),
+            };
+            let stdout = io::stdout();
+            bags.export(&mut stdout.lock(), format, scope)?;
         }
-        bags.insert(bag_name, bag);
+        None => part1(&bags, &args.bag_to_hold)?,
     }
-    part1(&bags);
+    Ok(())
 }
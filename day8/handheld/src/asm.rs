@@ -0,0 +1,187 @@
+// Assembler and disassembler of handheld programs.
+//
+// The base instruction set is the boot code format: one `operation offset` per line. The extended
+// one also allows comments from `#` or `;` to the end of the line, and labels: `name:` at the start
+// of a line names the instruction that follows it, and `jmp`, `nop`, `jz` and `jnz` can jump to a
+// name instead of an offset. Labels are resolved to offsets relative to the jumping instruction,
+// so assembled programs run on the same machine, and disassembling prints the canonical form
+// without labels or comments.
+
+use crate::vm::{Instruction, Isa, ParseError, Program};
+use std::collections::HashMap;
+use std::io::{self, BufRead, Write};
+
+/// An instruction whose operand may still be a label.
+struct Statement {
+    line: usize,
+    operation: String,
+    operand: String,
+}
+
+fn is_label(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|x| x.is_ascii_alphabetic() || x == '_')
+        && chars.all(|x| x.is_ascii_alphanumeric() || x == '_')
+}
+
+fn error(line: usize, message: String) -> ParseError {
+    ParseError { line, message }
+}
+
+/// Reads a program written for `isa`. Base programs are read by `Program::from_reader`; for the
+/// extended set, blank lines are skipped but still counted for the line numbers of errors.
+pub fn assemble<R: BufRead>(reader: R, isa: Isa) -> Result<Program, ParseError> {
+    if isa == Isa::Base {
+        return Program::from_reader(reader);
+    }
+    let mut statements = Vec::new();
+    let mut labels: HashMap<String, i64> = HashMap::new();
+    for (i, line) in reader.lines().enumerate() {
+        let line = line.map_err(|err| error(i + 1, err.to_string()))?;
+        let mut code = line.split(['#', ';']).next().unwrap_or("");
+        while let Some((label, rest)) = code.split_once(':') {
+            let label = label.trim();
+            if !is_label(label) {
+                return Err(error(i + 1, format!("invalid label: {}", label)));
+            }
+            if labels
+                .insert(label.to_string(), statements.len() as i64)
+                .is_some()
+            {
+                return Err(error(i + 1, format!("duplicate label: {}", label)));
+            }
+            code = rest;
+        }
+        if code.trim().is_empty() {
+            continue;
+        }
+        let mut iter = code.split_whitespace();
+        match (iter.next(), iter.next(), iter.next()) {
+            (Some(operation), Some(operand), None) => statements.push(Statement {
+                line: i + 1,
+                operation: operation.to_string(),
+                operand: operand.to_string(),
+            }),
+            _ => {
+                return Err(error(
+                    i + 1,
+                    format!("expected an operation and an operand: {}", line),
+                ))
+            }
+        }
+    }
+
+    let mut instructions = Vec::with_capacity(statements.len());
+    for (pc, statement) in statements.iter().enumerate() {
+        let Statement {
+            line,
+            operation,
+            operand,
+        } = statement;
+        let instruction = Instruction::from_parts(operation, 0, isa)
+            .ok_or_else(|| error(*line, format!("unknown operation: {}", operation)))?;
+        let operand = match operand.parse::<i64>() {
+            Ok(operand) => operand,
+            Err(_) if is_label(operand) => {
+                if !instruction.takes_offset() {
+                    return Err(error(
+                        *line,
+                        format!("{} takes a number, not a label", operation),
+                    ));
+                }
+                let target = labels
+                    .get(operand.as_str())
+                    .ok_or_else(|| error(*line, format!("undefined label: {}", operand)))?;
+                target - pc as i64
+            }
+            Err(_) => return Err(error(*line, format!("invalid operand: {}", operand))),
+        };
+        // Can't fail, the operation was already checked above
+        instructions.push(Instruction::from_parts(operation, operand, isa).unwrap());
+    }
+    Ok(Program::new(instructions))
+}
+
+/// Writes the program in its canonical form, one instruction per line with numeric offsets.
+pub fn disassemble<W: Write>(program: &Program, out: &mut W) -> io::Result<()> {
+    for instruction in program.instructions() {
+        writeln!(out, "{}", instruction)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vm::{Machine, StepOutcome};
+
+    const EXTENDED: &str = "\
+# doubles 3 twice
+start:  acc +3
+        mul +2          ; 6
+        jnz skip
+        acc +100
+skip:   mul +2
+        jz start
+        jmp end
+        acc -1
+end:
+";
+
+    #[test]
+    fn test_assemble_extended() {
+        let program = assemble(EXTENDED.as_bytes(), Isa::Extended).unwrap();
+        assert_eq!(program.len(), 8);
+        assert_eq!(program.get(2), Some(Instruction::Jnz(2)));
+        assert_eq!(program.get(5), Some(Instruction::Jz(-5)));
+        assert_eq!(program.get(6), Some(Instruction::Jmp(2)));
+        let mut machine = Machine::new(&program);
+        assert_eq!(machine.run(), StepOutcome::Halted);
+        assert_eq!(machine.acc, 12);
+
+        let program = assemble(
+            "acc +1\nnop end\njmp end\nacc +5\nend:\n".as_bytes(),
+            Isa::Extended,
+        )
+        .unwrap();
+        assert_eq!(program.get(2), Some(Instruction::Jmp(2)));
+        let mut machine = Machine::new(&program);
+        assert_eq!(machine.run(), StepOutcome::Halted);
+        assert_eq!(machine.acc, 1);
+    }
+
+    #[test]
+    fn test_disassemble() {
+        let program = assemble(EXTENDED.as_bytes(), Isa::Extended).unwrap();
+        let mut out = Vec::new();
+        disassemble(&program, &mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert_eq!(
+            out,
+            "acc +3\nmul +2\njnz +2\nacc +100\nmul +2\njz -5\njmp +2\nacc -1\n"
+        );
+        assert_eq!(assemble(out.as_bytes(), Isa::Extended), Ok(program));
+    }
+
+    #[test]
+    fn test_assemble_errors() {
+        let base = |source: &str| assemble(source.as_bytes(), Isa::Base).unwrap_err().message;
+        let extended = |source: &str| {
+            assemble(source.as_bytes(), Isa::Extended)
+                .unwrap_err()
+                .message
+        };
+        assert_eq!(base("jz +1"), "unknown operation: jz");
+        assert_eq!(base("jmp end"), "invalid operand: end");
+        assert_eq!(
+            base("nop +0 # comment"),
+            "expected an operation and an operand: nop +0 # comment"
+        );
+        assert_eq!(extended("jmp end"), "undefined label: end");
+        assert_eq!(extended("a: nop +0\na: nop +0"), "duplicate label: a");
+        assert_eq!(extended("a: acc a"), "acc takes a number, not a label");
+        assert_eq!(extended("1a: nop +0"), "invalid label: 1a");
+    }
+}
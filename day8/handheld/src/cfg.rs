@@ -0,0 +1,483 @@
+// Control-flow graph of a program, built without running it.
+//
+// A basic block starts at pc 0, at the target of any jump and right after any jump, and runs
+// straight to its last instruction. Conditional jumps have both successors, so for programs using
+// them the analysis answers what *may* happen; for boot code, where every instruction has a single
+// successor, the answers are exact.
+
+use crate::vm::{Instruction, Program, TerminationPolicy};
+use std::collections::BTreeSet;
+use std::io::{self, Write};
+
+/// Where control goes after a basic block.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Target {
+    Block(usize),
+    /// Right after the last instruction.
+    Halt,
+    /// A pc before the first instruction.
+    BeforeStart(i64),
+    /// A pc further than right after the last instruction.
+    PastEnd(i64),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BasicBlock {
+    /// First instruction of the block.
+    pub start: usize,
+    /// One past the last instruction of the block.
+    pub end: usize,
+    pub successors: Vec<Target>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Cfg<'a> {
+    program: &'a Program,
+    blocks: Vec<BasicBlock>,
+    block_of: Vec<usize>,
+    policy: TerminationPolicy,
+}
+
+/// Where control goes when the pc becomes `next`, with `block_of` mapping pcs to blocks.
+fn target(next: i64, block_of: &[usize]) -> Target {
+    if next < 0 {
+        Target::BeforeStart(next)
+    } else if next as usize == block_of.len() {
+        Target::Halt
+    } else if next as usize > block_of.len() {
+        Target::PastEnd(next)
+    } else {
+        Target::Block(block_of[next as usize])
+    }
+}
+
+/// The pcs control can go to after running `instruction` at `pc`. A jump whose target overflows
+/// stops the machine, so it goes nowhere.
+fn next_pcs(pc: i64, instruction: Instruction) -> Vec<i64> {
+    let targets = match instruction {
+        Instruction::Jmp(val) => vec![pc.checked_add(val)],
+        Instruction::Jz(val) | Instruction::Jnz(val) => {
+            vec![pc.checked_add(1), pc.checked_add(val)]
+        }
+        _ => vec![pc.checked_add(1)],
+    };
+    targets.into_iter().flatten().collect()
+}
+
+fn is_jump(instruction: Instruction) -> bool {
+    matches!(
+        instruction,
+        Instruction::Jmp(_) | Instruction::Jz(_) | Instruction::Jnz(_)
+    )
+}
+
+impl<'a> Cfg<'a> {
+    pub fn new(program: &'a Program) -> Cfg<'a> {
+        let len = program.len();
+        let mut leaders = BTreeSet::new();
+        if len > 0 {
+            leaders.insert(0);
+        }
+        for (pc, &instruction) in program.instructions().iter().enumerate() {
+            if !is_jump(instruction) {
+                continue;
+            }
+            for next in next_pcs(pc as i64, instruction) {
+                if next >= 0 && (next as usize) < len {
+                    leaders.insert(next as usize);
+                }
+            }
+            if pc + 1 < len {
+                leaders.insert(pc + 1);
+            }
+        }
+
+        let starts: Vec<usize> = leaders.into_iter().collect();
+        let mut block_of = vec![0; len];
+        for (block, &start) in starts.iter().enumerate() {
+            let end = starts.get(block + 1).copied().unwrap_or(len);
+            for x in &mut block_of[start..end] {
+                *x = block;
+            }
+        }
+        let blocks = starts
+            .iter()
+            .enumerate()
+            .map(|(block, &start)| {
+                let end = starts.get(block + 1).copied().unwrap_or(len);
+                let last = program.instructions()[end - 1];
+                let mut successors: Vec<Target> = next_pcs(end as i64 - 1, last)
+                    .into_iter()
+                    .map(|next| target(next, &block_of))
+                    .collect();
+                successors.sort_unstable();
+                successors.dedup();
+                BasicBlock {
+                    start,
+                    end,
+                    successors,
+                }
+            })
+            .collect();
+        Cfg {
+            program,
+            blocks,
+            block_of,
+            policy: TerminationPolicy::STRICT,
+        }
+    }
+
+    /// Uses `policy` to decide which ways of leaving the program are terminating, instead of
+    /// only running right past the last instruction.
+    pub fn with_policy(mut self, policy: TerminationPolicy) -> Cfg<'a> {
+        self.policy = policy;
+        self
+    }
+
+    /// Whether leaving the program through `target` terminates it.
+    fn terminates_at(&self, target: Target) -> bool {
+        match target {
+            Target::Block(_) => false,
+            Target::Halt => self.policy.at_end,
+            Target::BeforeStart(_) => self.policy.before_start,
+            Target::PastEnd(_) => self.policy.past_end,
+        }
+    }
+
+    pub fn blocks(&self) -> &[BasicBlock] {
+        &self.blocks
+    }
+
+    /// Block that contains the instruction at `pc`.
+    pub fn block_of(&self, pc: usize) -> Option<usize> {
+        self.block_of.get(pc).copied()
+    }
+
+    fn block_successors(&self, block: usize) -> impl Iterator<Item = usize> + '_ {
+        self.blocks[block]
+            .successors
+            .iter()
+            .filter_map(|x| match x {
+                Target::Block(block) => Some(*block),
+                _ => None,
+            })
+    }
+
+    /// For every block, whether it can be reached from the start of the program.
+    pub fn reachable_blocks(&self) -> Vec<bool> {
+        let mut reachable = vec![false; self.blocks.len()];
+        let mut stack = Vec::new();
+        if !self.blocks.is_empty() {
+            reachable[0] = true;
+            stack.push(0);
+        }
+        while let Some(block) = stack.pop() {
+            for next in self.block_successors(block) {
+                if !reachable[next] {
+                    reachable[next] = true;
+                    stack.push(next);
+                }
+            }
+        }
+        reachable
+    }
+
+    /// The pcs of the instructions that can never run.
+    pub fn unreachable_instructions(&self) -> Vec<usize> {
+        let reachable = self.reachable_blocks();
+        self.blocks
+            .iter()
+            .zip(reachable)
+            .filter(|(_, reachable)| !reachable)
+            .flat_map(|(block, _)| block.start..block.end)
+            .collect()
+    }
+
+    /// Strongly connected components of the blocks, with Tarjan's algorithm. Every component is
+    /// sorted, and components come after all the components they can reach.
+    pub fn sccs(&self) -> Vec<Vec<usize>> {
+        let n = self.blocks.len();
+        let mut index = vec![usize::MAX; n];
+        let mut lowlink = vec![0; n];
+        let mut on_stack = vec![false; n];
+        let mut stack = Vec::new();
+        let mut components = Vec::new();
+        let mut next_index = 0;
+        for root in 0..n {
+            if index[root] != usize::MAX {
+                continue;
+            }
+            // Each frame is a block and how many of its successors were already visited
+            let mut frames = vec![(root, 0)];
+            index[root] = next_index;
+            lowlink[root] = next_index;
+            next_index += 1;
+            stack.push(root);
+            on_stack[root] = true;
+            while let Some(&mut (block, ref mut visited)) = frames.last_mut() {
+                if let Some(next) = self.block_successors(block).nth(*visited) {
+                    *visited += 1;
+                    if index[next] == usize::MAX {
+                        index[next] = next_index;
+                        lowlink[next] = next_index;
+                        next_index += 1;
+                        stack.push(next);
+                        on_stack[next] = true;
+                        frames.push((next, 0));
+                    } else if on_stack[next] {
+                        lowlink[block] = lowlink[block].min(index[next]);
+                    }
+                    continue;
+                }
+                frames.pop();
+                if let Some(&(parent, _)) = frames.last() {
+                    lowlink[parent] = lowlink[parent].min(lowlink[block]);
+                }
+                if lowlink[block] == index[block] {
+                    let mut component = Vec::new();
+                    while let Some(x) = stack.pop() {
+                        on_stack[x] = false;
+                        component.push(x);
+                        if x == block {
+                            break;
+                        }
+                    }
+                    component.sort_unstable();
+                    components.push(component);
+                }
+            }
+        }
+        components
+    }
+
+    /// Components with a cycle that control can't leave once it gets in, so the program never
+    /// terminates after entering one of them.
+    pub fn infinite_loops(&self) -> Vec<Vec<usize>> {
+        self.sccs()
+            .into_iter()
+            .filter(|component| {
+                let has_cycle = component.len() > 1
+                    || self
+                        .block_successors(component[0])
+                        .any(|x| x == component[0]);
+                let closed = component.iter().all(|&block| {
+                    self.blocks[block].successors.iter().all(|x| match x {
+                        Target::Block(next) => component.binary_search(next).is_ok(),
+                        _ => false,
+                    })
+                });
+                has_cycle && closed
+            })
+            .collect()
+    }
+
+    /// For every pc from 0 to the length of the program (inclusive), whether the program can
+    /// terminate from there according to the policy.
+    pub fn can_terminate(&self) -> Vec<bool> {
+        let mut predecessors = vec![Vec::new(); self.blocks.len()];
+        let mut stack = Vec::new();
+        let mut terminates = vec![false; self.blocks.len()];
+        for (block, x) in self.blocks.iter().enumerate() {
+            for successor in &x.successors {
+                match *successor {
+                    Target::Block(next) => predecessors[next].push(block),
+                    exit if self.terminates_at(exit) && !terminates[block] => {
+                        terminates[block] = true;
+                        stack.push(block);
+                    }
+                    _ => {}
+                }
+            }
+        }
+        while let Some(block) = stack.pop() {
+            for &previous in &predecessors[block] {
+                if !terminates[previous] {
+                    terminates[previous] = true;
+                    stack.push(previous);
+                }
+            }
+        }
+        self.block_of
+            .iter()
+            .map(|&block| terminates[block])
+            .chain(std::iter::once(self.policy.at_end))
+            .collect()
+    }
+
+    /// The pcs of the `jmp` and `nop` instructions that run and whose flip makes the program
+    /// terminate according to the policy. Instructions from which the program already terminates
+    /// are left out, so the answer is exact for boot code that doesn't terminate as it is.
+    pub fn terminating_flips(&self) -> Vec<usize> {
+        let reachable = self.reachable_blocks();
+        let terminates = self.can_terminate();
+        self.program
+            .instructions()
+            .iter()
+            .enumerate()
+            .filter(|&(pc, _)| reachable[self.block_of[pc]] && !terminates[pc])
+            .filter_map(|(pc, instruction)| {
+                let next = instruction.flipped()?.next_pc(pc as i64, 0)?;
+                let terminates = match target(next, &self.block_of) {
+                    Target::Block(_) | Target::Halt => terminates[next as usize],
+                    exit => self.terminates_at(exit),
+                };
+                if terminates {
+                    Some(pc)
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Writes the graph in Graphviz format. Unreachable blocks are dashed, blocks of infinite
+    /// loops are red and flips that make the program terminate are green dotted edges.
+    pub fn write_dot<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        let reachable = self.reachable_blocks();
+        let looping: BTreeSet<usize> = self.infinite_loops().into_iter().flatten().collect();
+        writeln!(out, "digraph program {{")?;
+        writeln!(out, "    node [shape=box, fontname=\"monospace\"];")?;
+        let mut exits = BTreeSet::new();
+        for (block, x) in self.blocks.iter().enumerate() {
+            let label: String = (x.start..x.end)
+                .map(|pc| format!("{}: {}\\l", pc, self.program.instructions()[pc]))
+                .collect();
+            let mut attributes = format!("label=\"{}\"", label);
+            if !reachable[block] {
+                attributes.push_str(", style=dashed");
+            }
+            if looping.contains(&block) {
+                attributes.push_str(", color=red");
+            }
+            writeln!(out, "    b{} [{}];", block, attributes)?;
+            for successor in &x.successors {
+                writeln!(out, "    b{} -> {};", block, node_name(*successor))?;
+                if !matches!(successor, Target::Block(_)) {
+                    exits.insert(*successor);
+                }
+            }
+        }
+        for pc in self.terminating_flips() {
+            let instruction = self.program.instructions()[pc];
+            let flipped = instruction.flipped().unwrap();
+            // Flips that overflow the pc never terminate, so they aren't listed
+            let next = match flipped.next_pc(pc as i64, 0) {
+                Some(next) => next,
+                None => continue,
+            };
+            let target = target(next, &self.block_of);
+            if !matches!(target, Target::Block(_)) {
+                exits.insert(target);
+            }
+            writeln!(
+                out,
+                "    b{} -> {} [style=dotted, color=green, label=\"{}: {}\"];",
+                self.block_of[pc],
+                node_name(target),
+                pc,
+                flipped
+            )?;
+        }
+        for exit in exits {
+            match exit {
+                Target::Halt => writeln!(out, "    halt [shape=doublecircle];")?,
+                Target::BeforeStart(pc) | Target::PastEnd(pc) => writeln!(
+                    out,
+                    "    {} [shape=circle, color=red, label=\"pc {}\"];",
+                    node_name(exit),
+                    pc
+                )?,
+                Target::Block(_) => {}
+            }
+        }
+        writeln!(out, "}}")
+    }
+}
+
+fn node_name(target: Target) -> String {
+    match target {
+        Target::Block(block) => format!("b{}", block),
+        Target::Halt => "halt".to_string(),
+        Target::BeforeStart(pc) => format!("before_{}", pc.unsigned_abs()),
+        Target::PastEnd(pc) => format!("past_{}", pc),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str =
+        "nop +0\nacc +1\njmp +4\nacc +3\njmp -3\nacc -99\nacc +1\njmp -4\nacc +6\n";
+
+    #[test]
+    fn test_blocks() {
+        let program: Program = EXAMPLE.parse().unwrap();
+        let cfg = Cfg::new(&program);
+        let blocks: Vec<(usize, usize, Vec<Target>)> = cfg
+            .blocks()
+            .iter()
+            .map(|x| (x.start, x.end, x.successors.clone()))
+            .collect();
+        assert_eq!(
+            blocks,
+            vec![
+                (0, 1, vec![Target::Block(1)]),
+                (1, 3, vec![Target::Block(4)]),
+                (3, 5, vec![Target::Block(1)]),
+                (5, 6, vec![Target::Block(4)]),
+                (6, 8, vec![Target::Block(2)]),
+                (8, 9, vec![Target::Halt]),
+            ]
+        );
+        assert_eq!(cfg.block_of(7), Some(4));
+    }
+
+    #[test]
+    fn test_analysis() {
+        let program: Program = EXAMPLE.parse().unwrap();
+        let cfg = Cfg::new(&program);
+        assert_eq!(cfg.unreachable_instructions(), vec![5, 8]);
+        assert_eq!(cfg.infinite_loops(), vec![vec![1, 2, 4]]);
+        assert_eq!(cfg.terminating_flips(), vec![7]);
+
+        let program: Program = "nop +0\njmp +4\njmp -2\nacc +1\n".parse().unwrap();
+        let cfg = Cfg::new(&program);
+        assert_eq!(cfg.infinite_loops(), Vec::<Vec<usize>>::new());
+        assert_eq!(cfg.blocks()[0].successors, vec![Target::PastEnd(5)]);
+        assert_eq!(cfg.terminating_flips(), Vec::<usize>::new());
+        let cfg = cfg.with_policy(TerminationPolicy::LENIENT);
+        assert_eq!(cfg.can_terminate(), vec![true, true, true, true, true]);
+        // It terminates from everywhere, so there is nothing to flip
+        assert_eq!(cfg.terminating_flips(), Vec::<usize>::new());
+        assert_eq!(cfg.unreachable_instructions(), vec![2, 3]);
+    }
+
+    #[test]
+    fn test_dot() {
+        let program: Program = EXAMPLE.parse().unwrap();
+        let mut out = Vec::new();
+        Cfg::new(&program).write_dot(&mut out).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.starts_with("digraph program {\n"));
+        assert!(out.contains("    b1 [label=\"1: acc +1\\l2: jmp +4\\l\", color=red];\n"));
+        assert!(out.contains("    b3 [label=\"5: acc -99\\l\", style=dashed];\n"));
+        assert!(out.contains("    b4 -> b5 [style=dotted, color=green, label=\"7: nop -4\"];\n"));
+        assert!(out.contains("    halt [shape=doublecircle];\n"));
+    }
+
+    #[test]
+    fn test_dot_extreme_jumps() {
+        let program = Program::new(vec![Instruction::Jmp(i64::MIN), Instruction::Nop(i64::MAX)]);
+        let mut out = Vec::new();
+        Cfg::new(&program)
+            .with_policy(TerminationPolicy::LENIENT)
+            .write_dot(&mut out)
+            .unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert!(out.contains("    b0 -> before_9223372036854775808;\n"));
+        assert!(out.contains(
+            "    before_9223372036854775808 [shape=circle, color=red, label=\"pc -9223372036854775808\"];\n"
+        ));
+    }
+}
@@ -0,0 +1,283 @@
+// A small line-based debugger for the handheld VM. Commands are read one per line, so the same
+// code serves an interactive session on a terminal and a script piped from a file:
+//
+//     break N / b N     stop before running the instruction at N
+//     delete N / d N    remove the breakpoint at N
+//     step [N] / s [N]  run N instructions (1 by default)
+//     continue / c      run until a breakpoint or until the machine stops
+//     print / p         show the program counter and the accumulator
+//     trace / t         show the instructions that ran since the one that first repeats
+//     quit / q          stop reading commands
+
+use crate::vm::{Machine, Program, StepOutcome, TraceEntry};
+use std::collections::BTreeSet;
+use std::io::{self, BufRead, Write};
+
+pub struct Debugger<'a> {
+    machine: Machine<'a>,
+    breakpoints: BTreeSet<i64>,
+    trace: Vec<TraceEntry>,
+    stopped: Option<StepOutcome>,
+}
+
+impl<'a> Debugger<'a> {
+    pub fn new(program: &'a Program) -> Debugger<'a> {
+        Debugger {
+            machine: Machine::new(program),
+            breakpoints: BTreeSet::new(),
+            trace: Vec::new(),
+            stopped: None,
+        }
+    }
+
+    /// Stops the program after `limit` instructions, or never with `None`, instead of the
+    /// machine's default.
+    pub fn with_step_limit(mut self, limit: Option<u64>) -> Debugger<'a> {
+        self.machine = self.machine.with_step_limit(limit);
+        self
+    }
+
+    pub fn machine(&self) -> &Machine<'a> {
+        &self.machine
+    }
+
+    pub fn trace(&self) -> &[TraceEntry] {
+        &self.trace
+    }
+
+    /// Once a loop has been detected, the part of the trace from the first run of the repeated
+    /// instruction until the end, which is the body of the infinite loop.
+    pub fn loop_trace(&self) -> Option<&[TraceEntry]> {
+        if self.stopped != Some(StepOutcome::LoopDetected) {
+            return None;
+        }
+        let (pc, acc) = (self.machine.pc, self.machine.acc);
+        let start = if self.machine.tracks_accumulator() {
+            // The loop starts where the same instruction ran with the same accumulator before it
+            (0..self.trace.len()).find(|&i| {
+                let acc_before = if i == 0 { 0 } else { self.trace[i - 1].acc };
+                self.trace[i].pc == pc && acc_before == acc
+            })?
+        } else {
+            self.trace.iter().position(|x| x.pc == pc)?
+        };
+        Some(&self.trace[start..])
+    }
+
+    pub fn add_breakpoint(&mut self, pc: i64) {
+        self.breakpoints.insert(pc);
+    }
+
+    pub fn remove_breakpoint(&mut self, pc: i64) -> bool {
+        self.breakpoints.remove(&pc)
+    }
+
+    /// Runs one instruction, unless the program already stopped.
+    pub fn step(&mut self) -> StepOutcome {
+        if let Some(outcome) = self.stopped {
+            return outcome;
+        }
+        let outcome = self.machine.step_traced(&mut self.trace);
+        if outcome != StepOutcome::Continued {
+            self.stopped = Some(outcome);
+        }
+        outcome
+    }
+
+    /// Runs until the next breakpoint or until the program stops. Always runs at least one
+    /// instruction, so continuing from a breakpoint doesn't stop at it again.
+    pub fn cont(&mut self) -> StepOutcome {
+        loop {
+            let outcome = self.step();
+            if outcome != StepOutcome::Continued || self.breakpoints.contains(&self.machine.pc) {
+                return outcome;
+            }
+        }
+    }
+
+    fn report<W: Write>(&self, out: &mut W, outcome: StepOutcome) -> io::Result<()> {
+        match outcome {
+            StepOutcome::Continued if self.breakpoints.contains(&self.machine.pc) => {
+                writeln!(out, "breakpoint at {}", self.machine.pc)
+            }
+            StepOutcome::Continued => Ok(()),
+            StepOutcome::Halted => writeln!(out, "program halted"),
+            StepOutcome::LoopDetected => {
+                writeln!(out, "loop detected at {}", self.machine.pc)
+            }
+            StepOutcome::BeforeStart(pc) => {
+                writeln!(out, "jumped before the start of the program: {}", pc)
+            }
+            StepOutcome::PastEnd(pc) => {
+                writeln!(out, "jumped past the end of the program: {}", pc)
+            }
+            StepOutcome::Overflow => {
+                writeln!(out, "the instruction at {} overflows", self.machine.pc)
+            }
+            StepOutcome::StepLimit => writeln!(
+                out,
+                "stopped at {} after {} steps",
+                self.machine.pc,
+                self.machine.steps()
+            ),
+        }
+    }
+
+    /// Runs a single command, writing its output to `out`. Returns `false` when the command asks
+    /// to quit.
+    pub fn execute<W: Write>(&mut self, command: &str, out: &mut W) -> io::Result<bool> {
+        let mut iter = command.split_whitespace();
+        let name = match iter.next() {
+            Some(name) => name,
+            None => return Ok(true),
+        };
+        let argument = match iter.next().map(str::parse::<i64>) {
+            Some(Ok(argument)) => Some(argument),
+            Some(Err(_)) => {
+                writeln!(out, "invalid number in command: {}", command.trim())?;
+                return Ok(true);
+            }
+            None => None,
+        };
+        match (name, argument) {
+            ("break", Some(pc)) | ("b", Some(pc)) => {
+                self.add_breakpoint(pc);
+                writeln!(out, "breakpoint set at {}", pc)?;
+            }
+            ("delete", Some(pc)) | ("d", Some(pc)) => {
+                if !self.remove_breakpoint(pc) {
+                    writeln!(out, "no breakpoint at {}", pc)?;
+                }
+            }
+            ("step", n) | ("s", n) => {
+                for _ in 0..n.unwrap_or(1) {
+                    match self.step() {
+                        StepOutcome::Continued => {
+                            if let Some(entry) = self.trace.last() {
+                                writeln!(out, "{}", entry)?;
+                            }
+                        }
+                        outcome => {
+                            self.report(out, outcome)?;
+                            break;
+                        }
+                    }
+                }
+            }
+            ("continue", None) | ("c", None) => {
+                let outcome = self.cont();
+                self.report(out, outcome)?;
+            }
+            ("print", None) | ("p", None) => {
+                writeln!(out, "pc = {}, acc = {}", self.machine.pc, self.machine.acc)?;
+            }
+            ("trace", None) | ("t", None) => match self.loop_trace() {
+                Some(trace) => {
+                    for entry in trace {
+                        writeln!(out, "{}", entry)?;
+                    }
+                }
+                None => writeln!(out, "no instruction has repeated yet")?,
+            },
+            ("quit", None) | ("q", None) => return Ok(false),
+            _ => writeln!(out, "unknown command: {}", command.trim())?,
+        }
+        Ok(true)
+    }
+
+    /// Executes the commands read from `commands`, one per line, until the end or `quit`.
+    pub fn run_commands<R: BufRead, W: Write>(
+        &mut self,
+        commands: R,
+        out: &mut W,
+    ) -> io::Result<()> {
+        for command in commands.lines() {
+            if !self.execute(&command?, out)? {
+                break;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vm::Instruction;
+
+    const EXAMPLE: &str =
+        "nop +0\nacc +1\njmp +4\nacc +3\njmp -3\nacc -99\nacc +1\njmp -4\nacc +6\n";
+
+    fn run_script(script: &str) -> String {
+        let program: Program = EXAMPLE.parse().unwrap();
+        let mut debugger = Debugger::new(&program);
+        let mut out = Vec::new();
+        debugger.run_commands(script.as_bytes(), &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_breakpoints() {
+        let output = run_script("b 6\nc\np\nc\np\nq\np\n");
+        assert_eq!(
+            output,
+            "breakpoint set at 6\n\
+             breakpoint at 6\n\
+             pc = 6, acc = 1\n\
+             loop detected at 1\n\
+             pc = 1, acc = 5\n"
+        );
+    }
+
+    #[test]
+    fn test_step_and_trace() {
+        let output = run_script("s 2\nt\nc\nt\n");
+        let expected = format!(
+            "{}\n{}\nno instruction has repeated yet\nloop detected at 1\n{}\n{}\n{}\n{}\n{}\n{}\n",
+            "    0: nop +0     acc = 0",
+            "    1: acc +1     acc = 1",
+            "    1: acc +1     acc = 1",
+            "    2: jmp +4     acc = 1",
+            "    6: acc +1     acc = 2",
+            "    7: jmp -4     acc = 2",
+            "    3: acc +3     acc = 5",
+            "    4: jmp -3     acc = 5",
+        );
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_step_limit() {
+        let program = Program::new(vec![Instruction::Acc(1), Instruction::Jnz(-1)]);
+        let mut debugger = Debugger::new(&program).with_step_limit(Some(10));
+        let mut out = Vec::new();
+        debugger
+            .run_commands("c\np\n".as_bytes(), &mut out)
+            .unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "stopped at 0 after 10 steps\npc = 0, acc = 5\n"
+        );
+    }
+
+    #[test]
+    fn test_conditional_loop_trace() {
+        // Line 1 first runs with the accumulator at 1, and loops once it is back there with 0
+        let program = Program::new(vec![
+            Instruction::Acc(1),
+            Instruction::Nop(0),
+            Instruction::Jz(-1),
+            Instruction::Mul(0),
+            Instruction::Jmp(-3),
+        ]);
+        let mut debugger = Debugger::new(&program);
+        assert_eq!(debugger.cont(), StepOutcome::LoopDetected);
+        let pcs: Vec<(i64, i64)> = debugger
+            .loop_trace()
+            .unwrap()
+            .iter()
+            .map(|x| (x.pc, x.acc))
+            .collect();
+        assert_eq!(pcs, vec![(1, 0), (2, 0)]);
+    }
+}
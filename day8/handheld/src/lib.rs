@@ -0,0 +1,10 @@
+// Boot code of the handheld game console: a program is a list of `acc`, `jmp` and `nop`
+// instructions run by a machine with a program counter and a single accumulator. The extended
+// instruction set adds `mul`, `jz` and `jnz`, plus labels and comments in the assembler.
+// Programs can also be analysed statically through their control-flow graph.
+
+pub mod asm;
+pub mod cfg;
+pub mod debugger;
+pub mod repair;
+pub mod vm;
@@ -0,0 +1,210 @@
+// Exactly one `jmp` or `nop` is corrupted. Instead of trying every flip, find first the set of
+// instructions from which the unmodified program terminates, by walking backwards from the pcs
+// outside the program that the termination policy accepts. The program then runs once from the
+// start, and the first `jmp` or `nop` on its path whose flipped version lands in that set is the
+// repair. Both walks visit every instruction at most once, so this is linear in the length of the
+// program.
+
+use crate::vm::{Instruction, Machine, Program, TerminationPolicy};
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug, PartialEq)]
+pub enum RepairError {
+    /// The program already terminates without changes.
+    AlreadyTerminates(i64),
+    /// No single flip of a `jmp` or `nop` makes the program terminate.
+    NoRepair,
+    /// The instruction at this line jumps depending on the accumulator, so where the program goes
+    /// can't be known without running it.
+    ConditionalJump(usize),
+}
+
+impl fmt::Display for RepairError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RepairError::AlreadyTerminates(acc) => write!(
+                f,
+                "the program already terminates, with accumulator {}",
+                acc
+            ),
+            RepairError::NoRepair => {
+                write!(f, "no single jmp/nop flip makes the program terminate")
+            }
+            RepairError::ConditionalJump(line) => write!(
+                f,
+                "line {}: can't repair programs with conditional jumps",
+                line
+            ),
+        }
+    }
+}
+
+impl Error for RepairError {}
+
+#[derive(Debug, PartialEq)]
+pub struct Repair {
+    /// Index of the changed instruction.
+    pub pc: usize,
+    pub original: Instruction,
+    pub replacement: Instruction,
+    /// Accumulator once the repaired program terminates.
+    pub acc: i64,
+}
+
+impl Repair {
+    /// Line of the program that was changed, starting at 1.
+    pub fn line(&self) -> usize {
+        self.pc + 1
+    }
+}
+
+/// For every pc from 0 to the length of the program (inclusive), whether the unmodified program
+/// started there terminates according to `policy`. Conditional jumps are only followed as if the
+/// accumulator was 0.
+pub fn terminating_pcs(program: &Program, policy: TerminationPolicy) -> Vec<bool> {
+    let end = program.len();
+    let mut predecessors: Vec<Vec<usize>> = vec![Vec::new(); end + 1];
+    let mut terminates = vec![false; end + 1];
+    let mut stack = Vec::new();
+    if policy.at_end {
+        terminates[end] = true;
+        stack.push(end);
+    }
+    for (pc, instruction) in program.instructions().iter().enumerate() {
+        match instruction.next_pc(pc as i64, 0) {
+            Some(next) if next >= 0 && next as usize <= end => predecessors[next as usize].push(pc),
+            Some(next) if policy.accepts_pc(next, end) => {
+                terminates[pc] = true;
+                stack.push(pc);
+            }
+            _ => {}
+        }
+    }
+    while let Some(pc) = stack.pop() {
+        for &predecessor in &predecessors[pc] {
+            if !terminates[predecessor] {
+                terminates[predecessor] = true;
+                stack.push(predecessor);
+            }
+        }
+    }
+    terminates
+}
+
+/// Finds the single `jmp`/`nop` flip that makes the program terminate by running right past its
+/// last instruction.
+pub fn repair(program: &Program) -> Result<Repair, RepairError> {
+    repair_with(program, TerminationPolicy::STRICT)
+}
+
+/// Same as `repair`, with `policy` deciding which ways of leaving the program are terminating.
+pub fn repair_with(program: &Program, policy: TerminationPolicy) -> Result<Repair, RepairError> {
+    if let Some(pc) = program
+        .instructions()
+        .iter()
+        .position(|x| x.is_conditional())
+    {
+        return Err(RepairError::ConditionalJump(pc + 1));
+    }
+    let terminates = terminating_pcs(program, policy);
+    let leads_to_end = |pc: Option<i64>| match pc {
+        Some(pc) if pc >= 0 && (pc as usize) < terminates.len() => terminates[pc as usize],
+        Some(pc) => policy.accepts_pc(pc, program.len()),
+        None => false,
+    };
+
+    let mut trace = Vec::new();
+    let mut machine = Machine::new(program);
+    if policy.accepts(machine.run_traced(&mut trace)) {
+        return Err(RepairError::AlreadyTerminates(machine.acc));
+    }
+    let (pc, original, replacement) = trace
+        .iter()
+        .find_map(|entry| {
+            let replacement = entry.instruction.flipped()?;
+            if leads_to_end(replacement.next_pc(entry.pc, entry.acc)) {
+                Some((entry.pc as usize, entry.instruction, replacement))
+            } else {
+                None
+            }
+        })
+        .ok_or(RepairError::NoRepair)?;
+
+    let repaired = program.with_replaced(pc, replacement);
+    let mut machine = Machine::new(&repaired);
+    if policy.accepts(machine.run()) {
+        Ok(Repair {
+            pc,
+            original,
+            replacement,
+            acc: machine.acc,
+        })
+    } else {
+        Err(RepairError::NoRepair)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str =
+        "nop +0\nacc +1\njmp +4\nacc +3\njmp -3\nacc -99\nacc +1\njmp -4\nacc +6\n";
+
+    #[test]
+    fn test_repair() {
+        let program: Program = EXAMPLE.parse().unwrap();
+        let repair = repair(&program).unwrap();
+        assert_eq!(
+            repair,
+            Repair {
+                pc: 7,
+                original: Instruction::Jmp(-4),
+                replacement: Instruction::Nop(-4),
+                acc: 8
+            }
+        );
+        assert_eq!(repair.line(), 8);
+    }
+
+    #[test]
+    fn test_terminating_pcs() {
+        let program: Program = EXAMPLE.parse().unwrap();
+        let terminates: Vec<usize> = terminating_pcs(&program, TerminationPolicy::STRICT)
+            .iter()
+            .enumerate()
+            .filter(|(_, &x)| x)
+            .map(|(pc, _)| pc)
+            .collect();
+        assert_eq!(terminates, vec![8, 9]);
+    }
+
+    #[test]
+    fn test_repair_with_policy() {
+        // Only flipping the nop jumps out of the loop, before the start of the program
+        let program: Program = "acc +2\nnop -5\njmp -1\njmp +0\n".parse().unwrap();
+        assert_eq!(repair(&program), Err(RepairError::NoRepair));
+        let policy: TerminationPolicy = "end,before-start".parse().unwrap();
+        let repair = repair_with(&program, policy).unwrap();
+        assert_eq!(repair.line(), 2);
+        assert_eq!(repair.replacement, Instruction::Jmp(-5));
+        assert_eq!(repair.acc, 2);
+    }
+
+    #[test]
+    fn test_repair_errors() {
+        let program: Program = "acc +1\nnop +0\n".parse().unwrap();
+        assert_eq!(repair(&program), Err(RepairError::AlreadyTerminates(1)));
+        let program: Program = "acc +1\njmp -1\njmp -2\n".parse().unwrap();
+        assert_eq!(repair(&program), Err(RepairError::NoRepair));
+        let program: Program = "acc +1\njmp +5\njmp +0\n".parse().unwrap();
+        assert_eq!(repair(&program), Err(RepairError::NoRepair));
+        assert_eq!(
+            repair_with(&program, TerminationPolicy::LENIENT),
+            Err(RepairError::AlreadyTerminates(1))
+        );
+        let program = Program::new(vec![Instruction::Nop(0), Instruction::Jz(-1)]);
+        assert_eq!(repair(&program), Err(RepairError::ConditionalJump(2)));
+    }
+}
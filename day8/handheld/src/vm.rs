@@ -0,0 +1,723 @@
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt;
+use std::io::BufRead;
+use std::str::FromStr;
+
+/// Most instructions a machine runs a program with conditional jumps for, unless told otherwise.
+pub const DEFAULT_STEP_LIMIT: u64 = 1_000_000;
+
+/// Version of the instruction set a program is written for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Isa {
+    /// The boot code format: `acc`, `jmp` and `nop` with numeric offsets, one per line.
+    #[default]
+    Base,
+    /// Adds `mul`, `jz` and `jnz`, labels as jump targets and comments.
+    Extended,
+}
+
+impl FromStr for Isa {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Isa, String> {
+        match s {
+            "1" | "base" => Ok(Isa::Base),
+            "2" | "extended" => Ok(Isa::Extended),
+            _ => Err(format!(
+                "unknown instruction set {}, expected 1 (base) or 2 (extended)",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction {
+    Acc(i64),
+    Jmp(i64),
+    Nop(i64),
+    /// Multiplies the accumulator.
+    Mul(i64),
+    /// Jumps if the accumulator is zero.
+    Jz(i64),
+    /// Jumps if the accumulator isn't zero.
+    Jnz(i64),
+}
+
+impl Instruction {
+    /// Builds the instruction named `operation`, if it is part of `isa`.
+    pub fn from_parts(operation: &str, operand: i64, isa: Isa) -> Option<Instruction> {
+        let instruction = match operation {
+            "acc" => Instruction::Acc(operand),
+            "jmp" => Instruction::Jmp(operand),
+            "nop" => Instruction::Nop(operand),
+            "mul" => Instruction::Mul(operand),
+            "jz" => Instruction::Jz(operand),
+            "jnz" => Instruction::Jnz(operand),
+            _ => return None,
+        };
+        if instruction.isa() == Isa::Extended && isa == Isa::Base {
+            return None;
+        }
+        Some(instruction)
+    }
+
+    /// The oldest instruction set that has this instruction.
+    pub fn isa(self) -> Isa {
+        match self {
+            Instruction::Acc(_) | Instruction::Jmp(_) | Instruction::Nop(_) => Isa::Base,
+            Instruction::Mul(_) | Instruction::Jz(_) | Instruction::Jnz(_) => Isa::Extended,
+        }
+    }
+
+    pub fn operand(self) -> i64 {
+        match self {
+            Instruction::Acc(val)
+            | Instruction::Jmp(val)
+            | Instruction::Nop(val)
+            | Instruction::Mul(val)
+            | Instruction::Jz(val)
+            | Instruction::Jnz(val) => val,
+        }
+    }
+
+    /// Whether the operand is an offset to jump to, in which case it can be written as a label.
+    pub fn takes_offset(self) -> bool {
+        !matches!(self, Instruction::Acc(_) | Instruction::Mul(_))
+    }
+
+    /// Whether where the instruction jumps depends on the accumulator.
+    pub fn is_conditional(self) -> bool {
+        matches!(self, Instruction::Jz(_) | Instruction::Jnz(_))
+    }
+
+    /// Accumulator after running this instruction, or `None` if it overflows.
+    pub fn next_acc(self, acc: i64) -> Option<i64> {
+        match self {
+            Instruction::Acc(val) => acc.checked_add(val),
+            Instruction::Mul(val) => acc.checked_mul(val),
+            _ => Some(acc),
+        }
+    }
+
+    /// Program counter after running this instruction at `pc` with the accumulator `acc`, or
+    /// `None` if it overflows.
+    pub fn next_pc(self, pc: i64, acc: i64) -> Option<i64> {
+        match self {
+            Instruction::Jmp(val) => pc.checked_add(val),
+            Instruction::Jz(val) if acc == 0 => pc.checked_add(val),
+            Instruction::Jnz(val) if acc != 0 => pc.checked_add(val),
+            _ => pc.checked_add(1),
+        }
+    }
+
+    /// The instruction with `jmp` and `nop` swapped, or `None` for any other instruction.
+    pub fn flipped(self) -> Option<Instruction> {
+        match self {
+            Instruction::Jmp(val) => Some(Instruction::Nop(val)),
+            Instruction::Nop(val) => Some(Instruction::Jmp(val)),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for Instruction {
+    type Err = String;
+
+    /// Parses an instruction of the base instruction set.
+    fn from_str(s: &str) -> Result<Instruction, String> {
+        let mut iter = s.split_whitespace();
+        let (operation, operand) = match (iter.next(), iter.next(), iter.next()) {
+            (Some(operation), Some(operand), None) => (operation, operand),
+            _ => return Err(format!("expected an operation and an operand: {}", s)),
+        };
+        let operand: i64 = operand
+            .parse()
+            .map_err(|_| format!("invalid operand: {}", operand))?;
+        Instruction::from_parts(operation, operand, Isa::Base)
+            .ok_or_else(|| format!("unknown operation: {}", operation))
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let operation = match self {
+            Instruction::Acc(_) => "acc",
+            Instruction::Jmp(_) => "jmp",
+            Instruction::Nop(_) => "nop",
+            Instruction::Mul(_) => "mul",
+            Instruction::Jz(_) => "jz",
+            Instruction::Jnz(_) => "jnz",
+        };
+        write!(f, "{} {:+}", operation, self.operand())
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ParseError {
+    /// Line of the program where the error is, starting at 1.
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for ParseError {}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Program {
+    instructions: Vec<Instruction>,
+}
+
+impl Program {
+    pub fn new(instructions: Vec<Instruction>) -> Program {
+        Program { instructions }
+    }
+
+    /// Reads one instruction of the base instruction set per line. Blank lines are skipped but
+    /// still counted for the line numbers of errors.
+    pub fn from_reader<R: BufRead>(reader: R) -> Result<Program, ParseError> {
+        let mut instructions = Vec::new();
+        for (i, line) in reader.lines().enumerate() {
+            let line = line.map_err(|err| ParseError {
+                line: i + 1,
+                message: err.to_string(),
+            })?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let instruction = line.parse().map_err(|message| ParseError {
+                line: i + 1,
+                message,
+            })?;
+            instructions.push(instruction);
+        }
+        Ok(Program { instructions })
+    }
+
+    pub fn len(&self) -> usize {
+        self.instructions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.instructions.is_empty()
+    }
+
+    pub fn get(&self, pc: i64) -> Option<Instruction> {
+        if pc < 0 {
+            return None;
+        }
+        self.instructions.get(pc as usize).copied()
+    }
+
+    /// The oldest instruction set that can run the program.
+    pub fn isa(&self) -> Isa {
+        if self.instructions.iter().any(|x| x.isa() == Isa::Extended) {
+            Isa::Extended
+        } else {
+            Isa::Base
+        }
+    }
+
+    pub fn instructions(&self) -> &[Instruction] {
+        &self.instructions
+    }
+
+    /// A copy of the program with the instruction at `pc` replaced.
+    pub fn with_replaced(&self, pc: usize, instruction: Instruction) -> Program {
+        let mut instructions = self.instructions.clone();
+        instructions[pc] = instruction;
+        Program { instructions }
+    }
+}
+
+impl FromStr for Program {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Program, ParseError> {
+        Program::from_reader(s.as_bytes())
+    }
+}
+
+/// What happened when the machine tried to run one instruction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepOutcome {
+    /// An instruction ran and there are more to run.
+    Continued,
+    /// The program counter is right after the last instruction, so the program terminated.
+    Halted,
+    /// The machine is back in a state it was already in, so it wasn't run again. See `Machine`
+    /// for what counts as the same state.
+    LoopDetected,
+    /// Running the instruction at the program counter would overflow the accumulator or the
+    /// program counter, so it wasn't run.
+    Overflow,
+    /// The machine already ran as many instructions as its step limit allows.
+    StepLimit,
+    /// The program counter jumped before the first instruction.
+    BeforeStart(i64),
+    /// The program counter jumped further than right after the last instruction.
+    PastEnd(i64),
+}
+
+/// Which ways of leaving the program count as terminating successfully. Running into a loop, an
+/// overflow or the step limit never does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TerminationPolicy {
+    /// Running right past the last instruction, which is how boot code terminates.
+    pub at_end: bool,
+    pub before_start: bool,
+    pub past_end: bool,
+}
+
+impl TerminationPolicy {
+    /// Only running right past the last instruction terminates.
+    pub const STRICT: TerminationPolicy = TerminationPolicy {
+        at_end: true,
+        before_start: false,
+        past_end: false,
+    };
+    /// Leaving the program in any way terminates.
+    pub const LENIENT: TerminationPolicy = TerminationPolicy {
+        at_end: true,
+        before_start: true,
+        past_end: true,
+    };
+
+    pub fn accepts(self, outcome: StepOutcome) -> bool {
+        match outcome {
+            StepOutcome::Halted => self.at_end,
+            StepOutcome::BeforeStart(_) => self.before_start,
+            StepOutcome::PastEnd(_) => self.past_end,
+            StepOutcome::Continued
+            | StepOutcome::LoopDetected
+            | StepOutcome::Overflow
+            | StepOutcome::StepLimit => false,
+        }
+    }
+
+    /// Whether jumping to `pc` terminates a program of `len` instructions. Pcs inside the program
+    /// don't.
+    pub fn accepts_pc(self, pc: i64, len: usize) -> bool {
+        if pc < 0 {
+            self.before_start
+        } else if pc as usize == len {
+            self.at_end
+        } else if pc as usize > len {
+            self.past_end
+        } else {
+            false
+        }
+    }
+}
+
+impl Default for TerminationPolicy {
+    fn default() -> TerminationPolicy {
+        TerminationPolicy::STRICT
+    }
+}
+
+impl FromStr for TerminationPolicy {
+    type Err = String;
+
+    /// Accepts `strict`, `lenient` or a comma-separated list of `end`, `before-start` and
+    /// `past-end`.
+    fn from_str(s: &str) -> Result<TerminationPolicy, String> {
+        match s {
+            "strict" => return Ok(TerminationPolicy::STRICT),
+            "lenient" => return Ok(TerminationPolicy::LENIENT),
+            _ => {}
+        }
+        let mut policy = TerminationPolicy {
+            at_end: false,
+            before_start: false,
+            past_end: false,
+        };
+        for name in s.split(',') {
+            match name.trim() {
+                "end" => policy.at_end = true,
+                "before-start" => policy.before_start = true,
+                "past-end" => policy.past_end = true,
+                _ => {
+                    return Err(format!(
+                        "unknown termination {}, expected end, before-start or past-end",
+                        name
+                    ))
+                }
+            }
+        }
+        Ok(policy)
+    }
+}
+
+/// An instruction that ran, with the accumulator right after running it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TraceEntry {
+    pub pc: i64,
+    pub instruction: Instruction,
+    pub acc: i64,
+}
+
+impl fmt::Display for TraceEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let instruction = self.instruction.to_string();
+        write!(f, "{:>5}: {:<10} acc = {}", self.pc, instruction, self.acc)
+    }
+}
+
+/// Gets called by the machine for every instruction that runs.
+pub trait Tracer {
+    fn trace(&mut self, entry: TraceEntry);
+}
+
+/// Records the whole execution.
+impl Tracer for Vec<TraceEntry> {
+    fn trace(&mut self, entry: TraceEntry) {
+        self.push(entry);
+    }
+}
+
+/// Doesn't record anything, for running without tracing.
+pub struct NoTracer;
+
+impl Tracer for NoTracer {
+    fn trace(&mut self, _: TraceEntry) {}
+}
+
+/// The states the machine has been in, to stop before running into a loop.
+#[derive(Debug, Clone)]
+enum Visited {
+    /// Without conditional jumps, where the program goes doesn't depend on the accumulator, so
+    /// running any instruction a second time means the program loops forever.
+    Pcs(Vec<bool>),
+    /// With them, a loop can run again with a different accumulator and end, so the program only
+    /// loops forever once it is at the same instruction with the same accumulator.
+    States(HashSet<(i64, i64)>),
+}
+
+impl Visited {
+    /// Records the state, returning whether it is new.
+    fn insert(&mut self, pc: i64, acc: i64) -> bool {
+        match self {
+            Visited::Pcs(visited) => !std::mem::replace(&mut visited[pc as usize], true),
+            Visited::States(visited) => visited.insert((pc, acc)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Machine<'a> {
+    program: &'a Program,
+    pub pc: i64,
+    pub acc: i64,
+    visited: Visited,
+    steps: u64,
+    step_limit: Option<u64>,
+}
+
+impl<'a> Machine<'a> {
+    /// Programs without conditional jumps always stop within as many steps as they have
+    /// instructions, so they run without a step limit. Programs with them stop after
+    /// `DEFAULT_STEP_LIMIT` steps.
+    pub fn new(program: &'a Program) -> Machine<'a> {
+        let (visited, step_limit) = if program.instructions().iter().any(|x| x.is_conditional()) {
+            (Visited::States(HashSet::new()), Some(DEFAULT_STEP_LIMIT))
+        } else {
+            (Visited::Pcs(vec![false; program.len()]), None)
+        };
+        Machine {
+            program,
+            pc: 0,
+            acc: 0,
+            visited,
+            steps: 0,
+            step_limit,
+        }
+    }
+
+    /// Stops the machine with `StepOutcome::StepLimit` once it has run `limit` instructions, or
+    /// never with `None`.
+    pub fn with_step_limit(mut self, limit: Option<u64>) -> Machine<'a> {
+        self.step_limit = limit;
+        self
+    }
+
+    /// Number of instructions run so far.
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    /// Whether a loop is detected by the instruction and the accumulator repeating, as opposed
+    /// to only the instruction. That's the case for programs with conditional jumps, where a loop
+    /// that keeps changing the accumulator only stops at the step limit or when the accumulator
+    /// overflows.
+    pub fn tracks_accumulator(&self) -> bool {
+        matches!(self.visited, Visited::States(_))
+    }
+
+    pub fn program(&self) -> &'a Program {
+        self.program
+    }
+
+    pub fn step(&mut self) -> StepOutcome {
+        self.step_traced(&mut NoTracer)
+    }
+
+    /// Same as `step`, telling `tracer` about the instruction if one ran.
+    pub fn step_traced<T: Tracer + ?Sized>(&mut self, tracer: &mut T) -> StepOutcome {
+        let instruction = match self.program.get(self.pc) {
+            Some(instruction) => instruction,
+            None if self.pc < 0 => return StepOutcome::BeforeStart(self.pc),
+            None if self.pc == self.program.len() as i64 => return StepOutcome::Halted,
+            None => return StepOutcome::PastEnd(self.pc),
+        };
+        if self.step_limit.is_some_and(|x| self.steps >= x) {
+            return StepOutcome::StepLimit;
+        }
+        if !self.visited.insert(self.pc, self.acc) {
+            return StepOutcome::LoopDetected;
+        }
+        let (next_pc, next_acc) = match (
+            instruction.next_pc(self.pc, self.acc),
+            instruction.next_acc(self.acc),
+        ) {
+            (Some(next_pc), Some(next_acc)) => (next_pc, next_acc),
+            _ => return StepOutcome::Overflow,
+        };
+        self.steps += 1;
+        let pc = self.pc;
+        self.pc = next_pc;
+        self.acc = next_acc;
+        tracer.trace(TraceEntry {
+            pc,
+            instruction,
+            acc: self.acc,
+        });
+        StepOutcome::Continued
+    }
+
+    /// Steps until the program halts, loops, overflows, reaches the step limit or jumps out of
+    /// bounds.
+    pub fn run(&mut self) -> StepOutcome {
+        self.run_traced(&mut NoTracer)
+    }
+
+    pub fn run_traced<T: Tracer + ?Sized>(&mut self, tracer: &mut T) -> StepOutcome {
+        loop {
+            match self.step_traced(tracer) {
+                StepOutcome::Continued => continue,
+                outcome => return outcome,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asm::assemble;
+
+    fn assemble_extended(source: &str) -> Program {
+        assemble(source.as_bytes(), Isa::Extended).unwrap()
+    }
+
+    const EXAMPLE: &str = "nop +0
+acc +1
+jmp +4
+acc +3
+jmp -3
+acc -99
+acc +1
+jmp -4
+acc +6
+";
+
+    #[test]
+    fn test_parse() {
+        let program: Program = EXAMPLE.parse().unwrap();
+        assert_eq!(program.len(), 9);
+        assert_eq!(program.get(0), Some(Instruction::Nop(0)));
+        assert_eq!(program.get(5), Some(Instruction::Acc(-99)));
+        assert_eq!(program.get(9), None);
+        assert_eq!(Instruction::Jmp(-3).to_string(), "jmp -3");
+        assert_eq!(Instruction::Acc(6).to_string(), "acc +6");
+        assert_eq!(program.isa(), Isa::Base);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(
+            "nop +0\nmul +2\n".parse::<Program>(),
+            Err(ParseError {
+                line: 2,
+                message: "unknown operation: mul".to_string()
+            })
+        );
+        assert_eq!(
+            "nop +0\n\njmp x\n".parse::<Program>(),
+            Err(ParseError {
+                line: 3,
+                message: "invalid operand: x".to_string()
+            })
+        );
+        assert!("acc".parse::<Program>().is_err());
+    }
+
+    #[test]
+    fn test_run_until_loop() {
+        let program: Program = EXAMPLE.parse().unwrap();
+        let mut machine = Machine::new(&program);
+        assert_eq!(machine.run(), StepOutcome::LoopDetected);
+        assert_eq!(machine.acc, 5);
+        assert_eq!(machine.pc, 1);
+    }
+
+    #[test]
+    fn test_run_until_halt() {
+        let program: Program = EXAMPLE.replace("jmp -4", "nop -4").parse().unwrap();
+        let mut machine = Machine::new(&program);
+        assert_eq!(machine.run(), StepOutcome::Halted);
+        assert_eq!(machine.acc, 8);
+
+        let program: Program = "nop +0\njmp -2\n".parse().unwrap();
+        let mut machine = Machine::new(&program);
+        assert_eq!(machine.run(), StepOutcome::BeforeStart(-1));
+
+        let program: Program = "nop +0\njmp +2\n".parse().unwrap();
+        let mut machine = Machine::new(&program);
+        assert_eq!(machine.run(), StepOutcome::PastEnd(3));
+    }
+
+    #[test]
+    fn test_termination_policy() {
+        let strict = TerminationPolicy::STRICT;
+        assert!(strict.accepts(StepOutcome::Halted));
+        assert!(!strict.accepts(StepOutcome::BeforeStart(-1)));
+        assert!(!strict.accepts(StepOutcome::PastEnd(10)));
+        assert!(!TerminationPolicy::LENIENT.accepts(StepOutcome::LoopDetected));
+        let policy: TerminationPolicy = "end,past-end".parse().unwrap();
+        assert!(policy.accepts(StepOutcome::PastEnd(10)));
+        assert!(!policy.accepts(StepOutcome::BeforeStart(-1)));
+        assert!(policy.accepts_pc(12, 9));
+        assert!(!policy.accepts_pc(5, 9));
+        assert_eq!("lenient".parse(), Ok(TerminationPolicy::LENIENT));
+        assert!("end,sideways".parse::<TerminationPolicy>().is_err());
+    }
+
+    #[test]
+    fn test_extended_instructions() {
+        let program = Program::new(vec![
+            Instruction::Acc(3),
+            Instruction::Mul(-2),
+            Instruction::Jz(5),
+            Instruction::Jnz(2),
+            Instruction::Acc(100),
+            Instruction::Acc(6),
+            Instruction::Jz(-4),
+        ]);
+        assert_eq!(program.isa(), Isa::Extended);
+        // The jz at 2 runs again, but with a different accumulator
+        let mut machine = Machine::new(&program);
+        assert!(machine.tracks_accumulator());
+        assert_eq!(machine.run(), StepOutcome::Halted);
+        assert_eq!(machine.pc, 7);
+        assert_eq!(machine.acc, 0);
+        assert_eq!("3".parse::<Isa>().map_err(|_| ()), Err(()));
+        assert_eq!("extended".parse(), Ok(Isa::Extended));
+    }
+
+    #[test]
+    fn test_conditional_loops() {
+        // Counts down from 3, so the loop ends once the accumulator is 0
+        let program = Program::new(vec![
+            Instruction::Acc(3),
+            Instruction::Acc(-1),
+            Instruction::Jnz(-1),
+        ]);
+        let mut machine = Machine::new(&program);
+        assert_eq!(machine.run(), StepOutcome::Halted);
+        assert_eq!(machine.acc, 0);
+
+        // The accumulator doesn't change in the loop, so it never ends
+        let program = Program::new(vec![
+            Instruction::Acc(1),
+            Instruction::Mul(1),
+            Instruction::Jnz(-1),
+        ]);
+        let mut machine = Machine::new(&program);
+        assert_eq!(machine.run(), StepOutcome::LoopDetected);
+        assert_eq!((machine.pc, machine.acc), (1, 1));
+    }
+
+    #[test]
+    fn test_step_limit() {
+        // Counts up without ever repeating a state until the accumulator overflows
+        let program = assemble_extended("acc +1\njnz -1\n");
+        let mut machine = Machine::new(&program);
+        assert_eq!(machine.run(), StepOutcome::StepLimit);
+        assert_eq!(machine.steps(), DEFAULT_STEP_LIMIT);
+        assert_eq!(machine.acc, DEFAULT_STEP_LIMIT as i64 / 2);
+
+        let mut machine = Machine::new(&program).with_step_limit(Some(5));
+        assert_eq!(machine.run(), StepOutcome::StepLimit);
+        assert_eq!((machine.pc, machine.acc), (1, 3));
+        assert!(!TerminationPolicy::LENIENT.accepts(StepOutcome::StepLimit));
+
+        let program: Program = EXAMPLE.parse().unwrap();
+        let mut machine = Machine::new(&program).with_step_limit(Some(3));
+        assert_eq!(machine.run(), StepOutcome::StepLimit);
+        assert_eq!(machine.pc, 6);
+    }
+
+    #[test]
+    fn test_overflow() {
+        let program = Program::new(vec![
+            Instruction::Acc(1),
+            Instruction::Mul(1 << 62),
+            Instruction::Mul(2),
+        ]);
+        let mut machine = Machine::new(&program);
+        assert_eq!(machine.run(), StepOutcome::Overflow);
+        assert_eq!((machine.pc, machine.acc), (2, 1 << 62));
+
+        let program: Program = "acc +9223372036854775807\nacc +1\n".parse().unwrap();
+        let mut machine = Machine::new(&program);
+        assert_eq!(machine.run(), StepOutcome::Overflow);
+        assert_eq!((machine.pc, machine.acc), (1, i64::MAX));
+
+        // Running into the loop comes first
+        let program: Program = "acc +9223372036854775807\njmp -1\n".parse().unwrap();
+        let mut machine = Machine::new(&program);
+        assert_eq!(machine.run(), StepOutcome::LoopDetected);
+        assert_eq!((machine.pc, machine.acc), (0, i64::MAX));
+
+        let program: Program = "nop +0\njmp +9223372036854775807\n".parse().unwrap();
+        let mut machine = Machine::new(&program);
+        assert_eq!(machine.run(), StepOutcome::Overflow);
+        assert_eq!(machine.pc, 1);
+        assert!(!TerminationPolicy::LENIENT.accepts(StepOutcome::Overflow));
+    }
+
+    #[test]
+    fn test_trace() {
+        let program: Program = EXAMPLE.parse().unwrap();
+        let mut machine = Machine::new(&program);
+        let mut trace: Vec<TraceEntry> = Vec::new();
+        assert_eq!(machine.run_traced(&mut trace), StepOutcome::LoopDetected);
+        let pcs: Vec<i64> = trace.iter().map(|x| x.pc).collect();
+        assert_eq!(pcs, vec![0, 1, 2, 6, 7, 3, 4]);
+        assert_eq!(
+            trace[3],
+            TraceEntry {
+                pc: 6,
+                instruction: Instruction::Acc(1),
+                acc: 2
+            }
+        );
+    }
+}
@@ -65,66 +65,122 @@ value is in the accumulator?
 
 */
 
-use std::collections::HashSet;
+use handheld::asm::{assemble, disassemble};
+use handheld::cfg::Cfg;
+use handheld::debugger::Debugger;
+use handheld::vm::{Isa, Machine, Program, StepOutcome, TerminationPolicy};
+use std::env;
+use std::error::Error;
 use std::fs::File;
-use std::io::{self, BufRead};
-
-#[derive(Debug)]
-enum InstructionType {
-    Nop,
-    Acc(i64),
-    Jmp(i64),
+use std::io::{self, BufReader};
+
+const USAGE: &str = "usage: part1 [--isa 1|2] [--accept strict|lenient|end,before-start,past-end] \
+                     [--max-steps N] [--disassemble | --analyze | --cfg | --debug PROGRAM] \
+                     < PROGRAM";
+
+/// Runs the debugger commands read from stdin, so it can be used interactively or with a script
+/// piped in.
+fn debug(program: &Program, max_steps: Option<u64>) -> Result<(), Box<dyn Error>> {
+    let mut debugger = Debugger::new(program);
+    if max_steps.is_some() {
+        debugger = debugger.with_step_limit(max_steps);
+    }
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    debugger.run_commands(stdin.lock(), &mut stdout.lock())?;
+    Ok(())
 }
 
-fn convert_to_instruction_type(line: &str) -> Option<InstructionType> {
-    let mut iter = line.split_whitespace();
-    let instruction_type = iter.next();
-    let operand = iter.next();
-    match (instruction_type, operand) {
-        (Some(instruction), Some(val)) => match (instruction, val.parse::<i64>()) {
-            ("acc", Ok(arg)) => Some(InstructionType::Acc(arg)),
-            ("jmp", Ok(arg)) => Some(InstructionType::Jmp(arg)),
-            ("nop", Ok(_)) => Some(InstructionType::Nop),
-            (_, _) => None,
-        },
-        (_, _) => None,
+/// Prints what the control-flow graph tells about the program without running it, with lines
+/// starting at 1.
+fn analyze(program: &Program, policy: TerminationPolicy) {
+    let cfg = Cfg::new(program).with_policy(policy);
+    let lines = |pcs: &[usize]| -> String {
+        let lines: Vec<String> = pcs.iter().map(|x| (x + 1).to_string()).collect();
+        lines.join(", ")
+    };
+    println!("{} basic blocks", cfg.blocks().len());
+    println!(
+        "Unreachable lines: {}",
+        lines(&cfg.unreachable_instructions())
+    );
+    for blocks in cfg.infinite_loops() {
+        let pcs: Vec<usize> = blocks
+            .iter()
+            .flat_map(|&x| cfg.blocks()[x].start..cfg.blocks()[x].end)
+            .collect();
+        println!("Infinite loop over lines: {}", lines(&pcs));
     }
+    println!(
+        "Lines whose flip terminates: {}",
+        lines(&cfg.terminating_flips())
+    );
 }
 
-fn main() -> std::io::Result<()> {
-    let file = File::open("input")?;
-    let instructions: Vec<InstructionType> = io::BufReader::new(file)
-        .lines()
-        .filter_map(|x| x.ok())
-        .filter_map(|x| convert_to_instruction_type(&x))
-        .collect();
-
-    let mut accumulator: i64 = 0;
-    let mut instructions_processed: HashSet<usize> = HashSet::new();
-    let mut instruction_number: usize = 0;
-
-    loop {
-        if let Some(_) = instructions_processed.get(&instruction_number) {
-            break;
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut isa = Isa::Base;
+    let mut policy = TerminationPolicy::STRICT;
+    let mut debug_path = None;
+    let mut disassembling = false;
+    let mut analyzing = false;
+    let mut graphing = false;
+    let mut max_steps = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--isa" => isa = args.next().ok_or(USAGE)?.parse()?,
+            "--accept" => policy = args.next().ok_or(USAGE)?.parse()?,
+            "--max-steps" => max_steps = Some(args.next().ok_or(USAGE)?.parse()?),
+            "--debug" => debug_path = Some(args.next().ok_or(USAGE)?),
+            "--disassemble" => disassembling = true,
+            "--analyze" => analyzing = true,
+            "--cfg" => graphing = true,
+            _ => return Err(USAGE.into()),
+        }
+    }
+
+    // The debugger reads its commands from stdin, so the program comes from a file
+    if let Some(path) = debug_path {
+        let program = assemble(BufReader::new(File::open(path)?), isa)?;
+        return debug(&program, max_steps);
+    }
+
+    let stdin = io::stdin();
+    let program = assemble(stdin.lock(), isa)?;
+    if disassembling {
+        let stdout = io::stdout();
+        disassemble(&program, &mut stdout.lock())?;
+        return Ok(());
+    }
+    if analyzing {
+        analyze(&program, policy);
+        return Ok(());
+    }
+    if graphing {
+        let stdout = io::stdout();
+        Cfg::new(&program)
+            .with_policy(policy)
+            .write_dot(&mut stdout.lock())?;
+        return Ok(());
+    }
+    let mut machine = Machine::new(&program);
+    if max_steps.is_some() {
+        machine = machine.with_step_limit(max_steps);
+    }
+    match machine.run() {
+        StepOutcome::LoopDetected => println!("Accumulator is {}", machine.acc),
+        StepOutcome::StepLimit => println!(
+            "Program stopped after {} steps, accumulator is {}",
+            machine.steps(),
+            machine.acc
+        ),
+        outcome if policy.accepts(outcome) => {
+            println!("Program terminated, accumulator is {}", machine.acc)
         }
-        instructions_processed.insert(instruction_number);
-        match instructions.get(instruction_number) {
-            Some(InstructionType::Nop) => {
-                instruction_number += 1;
-            }
-            Some(InstructionType::Acc(val)) => {
-                accumulator += val;
-                instruction_number += 1;
-            }
-            Some(InstructionType::Jmp(val)) => {
-                instruction_number = (instruction_number as i64 + *val) as usize;
-            }
-            _ => panic!(
-                "No instructions found at instruction number: {}",
-                instruction_number
-            ),
-        };
+        outcome => println!(
+            "Program didn't loop ({:?}), accumulator is {}",
+            outcome, machine.acc
+        ),
     }
-    println!("Accumulator is {}", accumulator);
     Ok(())
 }
@@ -62,53 +62,35 @@
 // list (after the preamble) which is not the sum of two of the 25 numbers before it. What is the
 // first number that does not have this property?
 
-use std::collections::HashSet;
+use std::env;
+use std::error::Error;
 use std::io;
-use std::io::prelude::*;
-
-const PREAMBLE: usize = 25;
-
-fn part1(input: &[i64], queue: &mut Vec<i64>, set: &mut HashSet<i64>) -> Option<i64> {
-    for &num in input.iter().skip(PREAMBLE) {
-        let mut not_satisfied = true;
-        for v in queue.clone() {
-            if v < num && set.contains(&(num - v)) && num - v != v {
-                set.remove(&queue.remove(0));
-                queue.push(num);
-                set.insert(num);
-                not_satisfied = false;
-                break;
-            }
-        }
-        if not_satisfied {
-            // Test invariable
-            // for q in queue.clone() {
-            //     assert!(set.contains(&q));
-
-            // }
-            return Some(num);
+use xmas::{numbers, XmasValidator, DEFAULT_PREAMBLE};
+
+const USAGE: &str = "usage: part1 [--preamble N] [--all] < NUMBERS";
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut preamble = DEFAULT_PREAMBLE;
+    let mut all = false;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--preamble" => preamble = args.next().ok_or(USAGE)?.parse()?,
+            "--all" => all = true,
+            _ => return Err(USAGE.into()),
         }
     }
-    None
-}
 
-fn main() {
     let stdin = io::stdin();
-    let input: Vec<i64> = stdin
-        .lock()
-        .lines()
-        .filter_map(|x| x.ok())
-        .filter_map(|x| x.parse::<i64>().ok())
-        .collect();
-    let mut queue: Vec<i64> = input.iter().take(PREAMBLE).copied().collect();
-    let mut set: HashSet<i64> = input.iter().take(PREAMBLE).copied().collect();
-
-    // Invariable
-    // for q in queue.clone() {
-    //     assert!(set.contains(&q));
-    // }
-
-    if let Some(ans) = part1(&input, &mut queue, &mut set) {
-        println!("{}", ans);
+    let mut validator = XmasValidator::new(preamble);
+    for number in numbers(stdin.lock()) {
+        if let Some(invalid) = validator.push(number?) {
+            if !all {
+                println!("{}", invalid.value);
+                break;
+            }
+            println!("{}: {}", invalid.index, invalid.value);
+        }
     }
+    Ok(())
 }
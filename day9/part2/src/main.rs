@@ -95,64 +95,40 @@
 
 // What is the encryption weakness in your XMAS-encrypted list of numbers?
 
-use std::collections::HashSet;
+use std::env;
+use std::error::Error;
 use std::io;
-use std::io::prelude::*;
-
-const PREAMBLE: usize = 25;
-
-fn part1(input: &[i64], queue: &mut Vec<i64>, set: &mut HashSet<i64>) -> Option<(usize, i64)> {
-    for (i, &num) in input.iter().skip(PREAMBLE).enumerate() {
-        let mut not_satisfied = true;
-        for v in queue.clone() {
-            if v < num && set.contains(&(num - v)) && num - v != v {
-                set.remove(&queue.remove(0));
-                queue.push(num);
-                set.insert(num);
-                not_satisfied = false;
-                break;
-            }
-        }
-        if not_satisfied {
-            return Some((i, num));
+use xmas::range::{all_ranges, find_range, weakness};
+use xmas::{parse_numbers, XmasValidator, DEFAULT_PREAMBLE};
+
+const USAGE: &str = "usage: part2 [--preamble N] [--all-ranges] < NUMBERS";
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut preamble = DEFAULT_PREAMBLE;
+    let mut every_range = false;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--preamble" => preamble = args.next().ok_or(USAGE)?.parse()?,
+            "--all-ranges" => every_range = true,
+            _ => return Err(USAGE.into()),
         }
     }
-    None
-}
 
-fn part2(input: &[i64], invalid_num: i64) -> Option<i64> {
-    for i in 0..input.len() {
-        for set_length in 2..input.len() - i {
-            let possible_val: i64 = input.iter().skip(i).take(set_length).sum();
-            match possible_val.cmp(&invalid_num) {
-                std::cmp::Ordering::Greater => break,
-                std::cmp::Ordering::Equal => {
-                    let mut v: Vec<i64> = input[i..i + set_length].iter().copied().collect();
-                    v.sort_unstable();
-                    return Some(v.get(0).unwrap() + v.iter().last().unwrap());
-                }
-                _ => continue,
-            }
-        }
-    }
-    None
-}
-
-fn main() {
     let stdin = io::stdin();
-    let input: Vec<i64> = stdin
-        .lock()
-        .lines()
-        .filter_map(|x| x.ok())
-        .filter_map(|x| x.parse::<i64>().ok())
-        .collect();
-    let mut queue: Vec<i64> = input.iter().take(PREAMBLE).copied().collect();
-    let mut set: HashSet<i64> = input.iter().take(PREAMBLE).copied().collect();
-
-    if let Some(ans) = part1(&input, &mut queue, &mut set) {
-        println!("answer part1: {}", ans.1);
-        if let Some(ans2) = part2(&input[..ans.0], ans.1) {
-            println!("answer part2: {}", ans2);
+    let input = parse_numbers(stdin.lock())?;
+    let mut validator = XmasValidator::new(preamble);
+    let first_invalid = input.iter().find_map(|&x| validator.push(x));
+    if let Some(invalid) = first_invalid {
+        println!("answer part1: {}", invalid.value);
+        if every_range {
+            for range in all_ranges(&input, invalid.value) {
+                let ans2 = weakness(&input, range.clone()).unwrap();
+                println!("{}..{}: {}", range.start, range.end, ans2);
+            }
+        } else if let Some(range) = find_range(&input, invalid.value) {
+            println!("answer part2: {}", weakness(&input, range).unwrap());
         }
     }
+    Ok(())
 }
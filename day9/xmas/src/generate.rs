@@ -0,0 +1,262 @@
+// Generates XMAS streams with a known answer, to test the validator against. The preamble is a
+// shuffle of distinct small numbers, every later number is the sum of two different values picked
+// at random from the smaller half of the window before it, and corrupted positions get a number
+// that no pair in the window adds up to. Picking small values slows down how fast the numbers
+// grow, but with a preamble of only 2 or 3 numbers they still overflow after about a hundred.
+// Whether a number is a pair sum is checked here by brute force, so the ground truth doesn't
+// depend on the structures the validator uses.
+//
+// The random numbers come from SplitMix64, which is enough for test data and keeps the same seed
+// giving the same stream everywhere.
+
+use std::collections::BTreeSet;
+use std::error::Error;
+use std::fmt;
+
+#[derive(Debug, PartialEq)]
+pub enum GenerateError {
+    /// Valid numbers need two different values in the preamble.
+    PreambleTooShort(usize),
+    /// Numbers of the preamble are never invalid, so they can't be corrupted.
+    CorruptionInPreamble(usize),
+    CorruptionOutOfRange(usize),
+    /// The number at this index would overflow.
+    Overflow(usize),
+    /// Every number in the window before this index has the same value.
+    NoPair(usize),
+}
+
+impl fmt::Display for GenerateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GenerateError::PreambleTooShort(preamble) => {
+                write!(f, "a preamble of {} numbers can't have any pair", preamble)
+            }
+            GenerateError::CorruptionInPreamble(index) => {
+                write!(
+                    f,
+                    "can't corrupt number {}, it's part of the preamble",
+                    index
+                )
+            }
+            GenerateError::CorruptionOutOfRange(index) => {
+                write!(f, "can't corrupt number {}, it's past the end", index)
+            }
+            GenerateError::Overflow(index) => write!(f, "number {} overflows", index),
+            GenerateError::NoPair(index) => write!(
+                f,
+                "all the numbers before number {} have the same value",
+                index
+            ),
+        }
+    }
+}
+
+impl Error for GenerateError {}
+
+/// SplitMix64 random number generator.
+#[derive(Debug, Clone)]
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number from 0 to `n` (exclusive). `n` must not be 0.
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+}
+
+/// Whether two numbers in `window` with different values add up to `number`.
+fn is_pair_sum(window: &[i64], number: i64) -> bool {
+    window.iter().enumerate().any(|(i, &a)| {
+        window[i + 1..]
+            .iter()
+            .any(|&b| a != b && a.checked_add(b) == Some(number))
+    })
+}
+
+#[derive(Debug, Clone)]
+pub struct XmasGenerator {
+    preamble: usize,
+    length: usize,
+    seed: u64,
+    corruptions: BTreeSet<usize>,
+}
+
+impl XmasGenerator {
+    /// Generates `length` numbers, including a preamble of `preamble` numbers, with seed 0 and no
+    /// corruptions.
+    pub fn new(preamble: usize, length: usize) -> XmasGenerator {
+        XmasGenerator {
+            preamble,
+            length,
+            seed: 0,
+            corruptions: BTreeSet::new(),
+        }
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> XmasGenerator {
+        self.seed = seed;
+        self
+    }
+
+    /// Makes the numbers at `indices` invalid.
+    pub fn with_corruptions<I: IntoIterator<Item = usize>>(mut self, indices: I) -> XmasGenerator {
+        self.corruptions.extend(indices);
+        self
+    }
+
+    /// Indices of the invalid numbers, in order.
+    pub fn corruptions(&self) -> impl Iterator<Item = usize> + '_ {
+        self.corruptions.iter().copied()
+    }
+
+    pub fn generate(&self) -> Result<Vec<i64>, GenerateError> {
+        if self.preamble < 2 && self.length > self.preamble {
+            return Err(GenerateError::PreambleTooShort(self.preamble));
+        }
+        for &index in &self.corruptions {
+            if index < self.preamble {
+                return Err(GenerateError::CorruptionInPreamble(index));
+            }
+            if index >= self.length {
+                return Err(GenerateError::CorruptionOutOfRange(index));
+            }
+        }
+
+        let mut rng = Rng::new(self.seed);
+        // Fisher-Yates shuffle of 1 to twice the preamble, keeping the first numbers
+        let mut numbers: Vec<i64> = (1..=2 * self.preamble as i64).collect();
+        for i in 0..self.preamble.min(numbers.len()) {
+            let j = i + rng.below(numbers.len() - i);
+            numbers.swap(i, j);
+        }
+        numbers.truncate(self.preamble.min(self.length));
+
+        for index in self.preamble..self.length {
+            let window = &numbers[index - self.preamble..];
+            let number = if self.corruptions.contains(&index) {
+                let mut number = window[rng.below(window.len())];
+                // The window of the next number still needs two different values
+                while is_pair_sum(window, number) || window[1..].iter().all(|&x| x == number) {
+                    number = number
+                        .checked_add(1)
+                        .ok_or(GenerateError::Overflow(index))?;
+                }
+                number
+            } else {
+                let values: Vec<i64> = window
+                    .iter()
+                    .copied()
+                    .collect::<BTreeSet<i64>>()
+                    .into_iter()
+                    .collect();
+                if values.len() < 2 {
+                    return Err(GenerateError::NoPair(index));
+                }
+                let smaller = values.len().div_ceil(2).max(2);
+                let i = rng.below(smaller);
+                let j = (i + 1 + rng.below(smaller - 1)) % smaller;
+                values[i]
+                    .checked_add(values[j])
+                    .ok_or(GenerateError::Overflow(index))?
+            };
+            numbers.push(number);
+        }
+        Ok(numbers)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::XmasValidator;
+
+    #[test]
+    fn test_valid_stream() {
+        let generator = XmasGenerator::new(25, 200).with_seed(7);
+        let numbers = generator.generate().unwrap();
+        assert_eq!(numbers.len(), 200);
+        let preamble: BTreeSet<i64> = numbers[..25].iter().copied().collect();
+        assert_eq!(preamble.len(), 25);
+        assert!(XmasValidator::new(25)
+            .invalid_numbers(numbers.clone())
+            .is_empty());
+        assert_eq!(generator.generate(), Ok(numbers));
+    }
+
+    #[test]
+    fn test_random_corruptions() {
+        // Property: the validator finds exactly the corrupted positions, for any stream
+        let mut rng = Rng::new(2020);
+        for seed in 0..200 {
+            let preamble = 5 + rng.below(26);
+            let length = preamble + rng.below(150);
+            let corruptions: Vec<usize> = (0..rng.below(5))
+                .filter(|_| length > preamble)
+                .map(|_| preamble + rng.below(length - preamble))
+                .collect();
+            let generator = XmasGenerator::new(preamble, length)
+                .with_seed(seed)
+                .with_corruptions(corruptions);
+            let numbers = generator.generate().unwrap();
+            let found: Vec<usize> = XmasValidator::new(preamble)
+                .invalid_numbers(numbers)
+                .iter()
+                .map(|x| x.index)
+                .collect();
+            let expected: Vec<usize> = generator.corruptions().collect();
+            assert_eq!(found, expected, "seed {}, preamble {}", seed, preamble);
+        }
+    }
+
+    #[test]
+    fn test_short_preamble() {
+        let generator = XmasGenerator::new(2, 60)
+            .with_seed(1)
+            .with_corruptions(vec![2, 10, 11, 59]);
+        let numbers = generator.generate().unwrap();
+        let found: Vec<usize> = XmasValidator::new(2)
+            .invalid_numbers(numbers)
+            .iter()
+            .map(|x| x.index)
+            .collect();
+        assert_eq!(found, vec![2, 10, 11, 59]);
+        assert!(matches!(
+            XmasGenerator::new(2, 200).generate(),
+            Err(GenerateError::Overflow(_))
+        ));
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            XmasGenerator::new(1, 5).generate(),
+            Err(GenerateError::PreambleTooShort(1))
+        );
+        assert_eq!(
+            XmasGenerator::new(5, 10)
+                .with_corruptions(vec![3])
+                .generate(),
+            Err(GenerateError::CorruptionInPreamble(3))
+        );
+        assert_eq!(
+            XmasGenerator::new(5, 10)
+                .with_corruptions(vec![10])
+                .generate(),
+            Err(GenerateError::CorruptionOutOfRange(10))
+        );
+        assert_eq!(XmasGenerator::new(1, 1).generate().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,176 @@
+// XMAS data is a stream of numbers where, after a preamble, every number must be the sum of two
+// numbers with different values among the ones right before it. The validator keeps that window
+// of previous numbers as the stream goes, so it never needs the whole list in memory.
+
+pub mod generate;
+pub mod range;
+pub mod window;
+
+use std::error::Error;
+use std::fmt;
+use std::io::BufRead;
+use window::PairWindow;
+
+/// Length of the preamble, and of the window of previous numbers, in the real XMAS data.
+pub const DEFAULT_PREAMBLE: usize = 25;
+
+#[derive(Debug, PartialEq)]
+pub enum XmasError {
+    Parse { line: usize, text: String },
+    Io(String),
+}
+
+impl fmt::Display for XmasError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            XmasError::Parse { line, text } => write!(f, "line {}: invalid number: {}", line, text),
+            XmasError::Io(err) => write!(f, "couldn't read the numbers: {}", err),
+        }
+    }
+}
+
+impl Error for XmasError {}
+
+/// Reads one number per line as they are needed, skipping blank lines.
+pub fn numbers<R: BufRead>(reader: R) -> impl Iterator<Item = Result<i64, XmasError>> {
+    reader
+        .lines()
+        .enumerate()
+        .filter_map(|(i, line)| match line {
+            Ok(line) if line.trim().is_empty() => None,
+            Ok(line) => Some(line.trim().parse().map_err(|_| XmasError::Parse {
+                line: i + 1,
+                text: line.trim().to_string(),
+            })),
+            Err(err) => Some(Err(XmasError::Io(err.to_string()))),
+        })
+}
+
+/// Reads all the numbers, one per line, skipping blank lines.
+pub fn parse_numbers<R: BufRead>(reader: R) -> Result<Vec<i64>, XmasError> {
+    numbers(reader).collect()
+}
+
+/// A number that isn't the sum of two of the numbers before it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidNumber {
+    /// Position of the number in the stream, starting at 0.
+    pub index: usize,
+    pub value: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct XmasValidator {
+    window: PairWindow,
+    index: usize,
+}
+
+impl XmasValidator {
+    /// A validator where the preamble, and the window every number is checked against, has
+    /// `preamble` numbers.
+    pub fn new(preamble: usize) -> XmasValidator {
+        XmasValidator {
+            window: PairWindow::new(preamble),
+            index: 0,
+        }
+    }
+
+    pub fn preamble(&self) -> usize {
+        self.window.capacity()
+    }
+
+    /// Takes the next number of the stream, returning it if it's invalid. Numbers of the preamble
+    /// are always valid. Invalid numbers still become part of the window.
+    pub fn push(&mut self, number: i64) -> Option<InvalidNumber> {
+        let index = self.index;
+        self.index += 1;
+        let invalid = index >= self.preamble() && !self.window.has_pair_sum(number);
+        self.window.push(number);
+        if invalid {
+            Some(InvalidNumber {
+                index,
+                value: number,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Every invalid number in `numbers`, in order.
+    pub fn invalid_numbers<I: IntoIterator<Item = i64>>(
+        &mut self,
+        numbers: I,
+    ) -> Vec<InvalidNumber> {
+        numbers.into_iter().filter_map(|x| self.push(x)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use std::io::BufReader;
+
+    fn example_numbers() -> Vec<i64> {
+        let file = File::open("../example_input").unwrap();
+        parse_numbers(BufReader::new(file)).unwrap()
+    }
+
+    #[test]
+    fn test_example_input() {
+        let numbers = example_numbers();
+        assert_eq!(numbers.len(), 20);
+        let mut validator = XmasValidator::new(5);
+        assert_eq!(
+            validator.invalid_numbers(numbers),
+            vec![InvalidNumber {
+                index: 14,
+                value: 127
+            }]
+        );
+    }
+
+    #[test]
+    fn test_every_invalid_number() {
+        // 1 to 25 in order, as in the examples of the puzzle
+        let mut validator = XmasValidator::new(DEFAULT_PREAMBLE);
+        assert!(validator.invalid_numbers(1..=25).is_empty());
+        assert_eq!(
+            validator.invalid_numbers(vec![26, 100, 50, 3]),
+            vec![
+                InvalidNumber {
+                    index: 26,
+                    value: 100
+                },
+                InvalidNumber {
+                    index: 28,
+                    value: 3
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_distinct_values() {
+        let mut validator = XmasValidator::new(3);
+        assert_eq!(
+            validator.invalid_numbers(vec![5, 5, 1, 10, 6]),
+            vec![InvalidNumber {
+                index: 3,
+                value: 10
+            }]
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(parse_numbers("1\n\n2\n".as_bytes()), Ok(vec![1, 2]));
+        assert_eq!(
+            parse_numbers("1\nx\n".as_bytes()),
+            Err(XmasError::Parse {
+                line: 2,
+                text: "x".to_string()
+            })
+        );
+    }
+}
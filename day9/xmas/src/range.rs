@@ -0,0 +1,142 @@
+// Contiguous ranges of at least two numbers that add up to a target. With prefix sums, the range
+// start..end adds up to prefix[end] - prefix[start], so for every end the starts that work are the
+// ones whose prefix sum is prefix[end] - target, which a hash map from prefix sums to positions
+// answers directly. When no number is negative the sum of a window only grows as it widens, and
+// two pointers find the first range without the map.
+//
+// Ranges are ordered by where they end and then by where they start, so the first range is the
+// one that ends first, and the longest of those.
+
+use std::collections::HashMap;
+use std::ops::Range;
+
+/// The first range of at least two numbers that adds up to `target`.
+pub fn find_range(numbers: &[i64], target: i64) -> Option<Range<usize>> {
+    if numbers.iter().all(|&x| x >= 0) {
+        find_range_non_negative(numbers, target)
+    } else {
+        find_range_prefix_sums(numbers, target)
+    }
+}
+
+/// Same as `find_range`, stopping at the first end that works. Only the first position of every
+/// prefix sum is kept, since that start gives the longest range.
+fn find_range_prefix_sums(numbers: &[i64], target: i64) -> Option<Range<usize>> {
+    let mut first_start: HashMap<i128, usize> = HashMap::new();
+    // prefix[end - 2], prefix[end - 1] and prefix[end] as `end` moves forward
+    let mut prefix: [i128; 3] = [0, 0, 0];
+    for (i, &x) in numbers.iter().enumerate() {
+        let end = i + 1;
+        prefix = [prefix[1], prefix[2], prefix[2] + i128::from(x)];
+        if end < 2 {
+            continue;
+        }
+        // Only starts at least two numbers before `end` can be used from here on
+        first_start.entry(prefix[0]).or_insert(end - 2);
+        if let Some(&start) = first_start.get(&(prefix[2] - i128::from(target))) {
+            return Some(start..end);
+        }
+    }
+    None
+}
+
+/// Same as `find_range`, for numbers that are all zero or positive.
+fn find_range_non_negative(numbers: &[i64], target: i64) -> Option<Range<usize>> {
+    let target = i128::from(target);
+    let mut start = 0;
+    let mut sum: i128 = 0;
+    for (end, &x) in numbers.iter().enumerate() {
+        sum += i128::from(x);
+        while sum > target && start <= end {
+            sum -= i128::from(numbers[start]);
+            start += 1;
+        }
+        if sum == target && end > start {
+            return Some(start..end + 1);
+        }
+    }
+    None
+}
+
+/// Every range of at least two numbers that adds up to `target`. Works with negative numbers.
+pub fn all_ranges(numbers: &[i64], target: i64) -> Vec<Range<usize>> {
+    let mut prefix: Vec<i128> = Vec::with_capacity(numbers.len() + 1);
+    prefix.push(0);
+    for &x in numbers {
+        prefix.push(prefix[prefix.len() - 1] + i128::from(x));
+    }
+    let mut starts: HashMap<i128, Vec<usize>> = HashMap::new();
+    let mut ranges = Vec::new();
+    for end in 2..prefix.len() {
+        // Only starts at least two numbers before `end` can be used from here on
+        starts.entry(prefix[end - 2]).or_default().push(end - 2);
+        if let Some(found) = starts.get(&(prefix[end] - i128::from(target))) {
+            ranges.extend(found.iter().map(|&start| start..end));
+        }
+    }
+    ranges
+}
+
+/// Sum of the smallest and the largest number in the range.
+pub fn weakness(numbers: &[i64], range: Range<usize>) -> Option<i64> {
+    let range = &numbers[range];
+    Some(range.iter().min()? + range.iter().max()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: [i64; 20] = [
+        35, 20, 15, 25, 47, 40, 62, 55, 65, 95, 102, 117, 150, 182, 127, 219, 299, 277, 309, 576,
+    ];
+
+    #[test]
+    fn test_example_input() {
+        assert_eq!(find_range(&EXAMPLE, 127), Some(2..6));
+        assert_eq!(weakness(&EXAMPLE, 2..6), Some(62));
+        assert_eq!(all_ranges(&EXAMPLE, 127), vec![2..6]);
+        // 127 alone is not a range
+        assert_eq!(find_range(&EXAMPLE[14..], 127), None);
+    }
+
+    #[test]
+    fn test_zeros() {
+        assert_eq!(find_range(&[127, 0], 127), Some(0..2));
+        assert_eq!(find_range(&[1, 0, 0, 4], 5), Some(0..4));
+        assert_eq!(find_range(&[3, 0, 2], 0), None);
+    }
+
+    #[test]
+    fn test_negative_numbers() {
+        let numbers = [5, -3, 8, 2, -2, 7];
+        assert_eq!(find_range(&numbers, 10), Some(0..3));
+        assert_eq!(all_ranges(&numbers, 10), vec![0..3, 2..4, 0..5]);
+        assert_eq!(weakness(&numbers, 0..5), Some(5));
+        assert_eq!(find_range(&[4, -1, 1, 6], 10), Some(0..4));
+        assert_eq!(find_range(&[-1, 1], 0), Some(0..2));
+        assert_eq!(find_range(&[5, -5], 5), None);
+    }
+
+    #[test]
+    fn test_find_range_matches_all_ranges() {
+        let numbers: Vec<i64> = (0..200).map(|x| (x * 37 % 23) - 11).collect();
+        for target in -30..30 {
+            assert_eq!(
+                find_range(&numbers, target),
+                all_ranges(&numbers, target).into_iter().next()
+            );
+        }
+    }
+
+    #[test]
+    fn test_many_ranges() {
+        // Every pair of 1, -1 starts a new range adding up to 0, so there are quadratically many
+        let mut numbers = vec![1, 2, 3, 4, 5, 0];
+        for _ in 0..15000 {
+            numbers.extend([1, -1].iter());
+        }
+        assert_eq!(find_range(&numbers, 15), Some(0..5));
+        assert_eq!(find_range(&numbers, 0), Some(5..8));
+    }
+}
@@ -0,0 +1,123 @@
+// The last `capacity` numbers of the stream, kept in a ring buffer so adding one overwrites the
+// oldest in place, plus a count of every value in it. The counts make "is there a number equal to
+// x" a single lookup even when the same value appears several times, so looking for a pair with a
+// given sum only walks the window once.
+
+use std::collections::HashMap;
+
+#[derive(Debug, Clone)]
+pub struct PairWindow {
+    capacity: usize,
+    values: Vec<i64>,
+    /// Position in `values` of the oldest number once the window is full.
+    start: usize,
+    counts: HashMap<i64, usize>,
+}
+
+impl PairWindow {
+    pub fn new(capacity: usize) -> PairWindow {
+        PairWindow {
+            capacity,
+            values: Vec::with_capacity(capacity),
+            start: 0,
+            counts: HashMap::with_capacity(capacity),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.values.len() == self.capacity
+    }
+
+    pub fn contains(&self, value: i64) -> bool {
+        self.counts.contains_key(&value)
+    }
+
+    /// Adds `value` as the newest number, returning the oldest one if it had to make room.
+    pub fn push(&mut self, value: i64) -> Option<i64> {
+        if self.capacity == 0 {
+            return None;
+        }
+        *self.counts.entry(value).or_insert(0) += 1;
+        if !self.is_full() {
+            self.values.push(value);
+            return None;
+        }
+        let oldest = std::mem::replace(&mut self.values[self.start], value);
+        self.start = (self.start + 1) % self.values.len();
+        if let Some(count) = self.counts.get_mut(&oldest) {
+            *count -= 1;
+            if *count == 0 {
+                self.counts.remove(&oldest);
+            }
+        }
+        Some(oldest)
+    }
+
+    /// Whether two numbers in the window with different values add up to `sum`.
+    pub fn has_pair_sum(&self, sum: i64) -> bool {
+        self.values.iter().any(|&a| match sum.checked_sub(a) {
+            Some(b) => b != a && self.contains(b),
+            None => false,
+        })
+    }
+
+    /// The numbers from the oldest to the newest.
+    pub fn iter(&self) -> impl Iterator<Item = i64> + '_ {
+        let (newest, oldest) = self.values.split_at(self.start);
+        oldest.iter().chain(newest).copied()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ring_buffer() {
+        let mut window = PairWindow::new(3);
+        assert_eq!(window.push(1), None);
+        assert_eq!(window.push(2), None);
+        assert_eq!(window.push(3), None);
+        assert!(window.is_full());
+        assert_eq!(window.push(4), Some(1));
+        assert_eq!(window.push(5), Some(2));
+        assert_eq!(window.iter().collect::<Vec<i64>>(), vec![3, 4, 5]);
+        assert!(!window.contains(1));
+        assert_eq!(window.capacity(), 3);
+
+        let mut window = PairWindow::new(0);
+        assert_eq!(window.push(1), None);
+        assert!(window.is_empty());
+    }
+
+    #[test]
+    fn test_duplicates() {
+        let mut window = PairWindow::new(3);
+        for x in [5, 5, 1] {
+            window.push(x);
+        }
+        assert!(!window.has_pair_sum(10));
+        assert!(window.has_pair_sum(6));
+        // One of the fives leaves but the other one is still there
+        window.push(7);
+        assert!(window.contains(5));
+        assert!(window.has_pair_sum(12));
+        window.push(7);
+        assert!(!window.contains(5));
+        assert!(!window.has_pair_sum(6));
+        assert!(!window.has_pair_sum(14));
+        assert!(window.has_pair_sum(8));
+    }
+}